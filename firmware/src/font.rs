@@ -0,0 +1,102 @@
+// 5x5 glyphs and icons for the LED matrix. Each row is five bits,
+// the highest bit being the leftmost LED.
+
+use microbit_protocol::Icon;
+
+pub type Frame = [[u8; 5]; 5];
+
+pub const BLANK: Frame = [[0; 5]; 5];
+
+// Columns per character, including the gap after it
+const GLYPH_STRIDE: usize = 6;
+
+fn glyph(c: u8) -> [u8; 5] {
+    match c.to_ascii_uppercase() {
+        b'0' => [0b01110, 0b10011, 0b10101, 0b11001, 0b01110],
+        b'1' => [0b00100, 0b01100, 0b00100, 0b00100, 0b01110],
+        b'2' => [0b11110, 0b00001, 0b01110, 0b10000, 0b11111],
+        b'3' => [0b11110, 0b00001, 0b00110, 0b00001, 0b11110],
+        b'4' => [0b00110, 0b01010, 0b10010, 0b11111, 0b00010],
+        b'5' => [0b11111, 0b10000, 0b11110, 0b00001, 0b11110],
+        b'6' => [0b00010, 0b00100, 0b01110, 0b10001, 0b01110],
+        b'7' => [0b11111, 0b00010, 0b00100, 0b01000, 0b10000],
+        b'8' => [0b01110, 0b10001, 0b01110, 0b10001, 0b01110],
+        b'9' => [0b01110, 0b10001, 0b01110, 0b00100, 0b01000],
+        b'A' => [0b01100, 0b10010, 0b11110, 0b10010, 0b10010],
+        b'B' => [0b11100, 0b10010, 0b11100, 0b10010, 0b11100],
+        b'C' => [0b01110, 0b10000, 0b10000, 0b10000, 0b01110],
+        b'D' => [0b11100, 0b10010, 0b10010, 0b10010, 0b11100],
+        b'E' => [0b11110, 0b10000, 0b11100, 0b10000, 0b11110],
+        b'F' => [0b11110, 0b10000, 0b11100, 0b10000, 0b10000],
+        b'G' => [0b01110, 0b10000, 0b10011, 0b10001, 0b01110],
+        b'H' => [0b10010, 0b10010, 0b11110, 0b10010, 0b10010],
+        b'I' => [0b11100, 0b01000, 0b01000, 0b01000, 0b11100],
+        b'J' => [0b11110, 0b00010, 0b00010, 0b10010, 0b01100],
+        b'K' => [0b10010, 0b10100, 0b11000, 0b10100, 0b10010],
+        b'L' => [0b10000, 0b10000, 0b10000, 0b10000, 0b11110],
+        b'M' => [0b10001, 0b11011, 0b10101, 0b10001, 0b10001],
+        b'N' => [0b10001, 0b11001, 0b10101, 0b10011, 0b10001],
+        b'O' => [0b01100, 0b10010, 0b10010, 0b10010, 0b01100],
+        b'P' => [0b11100, 0b10010, 0b11100, 0b10000, 0b10000],
+        b'Q' => [0b01100, 0b10010, 0b10010, 0b01100, 0b00110],
+        b'R' => [0b11100, 0b10010, 0b11100, 0b10010, 0b10001],
+        b'S' => [0b01110, 0b10000, 0b01100, 0b00010, 0b11100],
+        b'T' => [0b11111, 0b00100, 0b00100, 0b00100, 0b00100],
+        b'U' => [0b10010, 0b10010, 0b10010, 0b10010, 0b01100],
+        b'V' => [0b10001, 0b10001, 0b10001, 0b01010, 0b00100],
+        b'W' => [0b10001, 0b10001, 0b10101, 0b11011, 0b10001],
+        b'X' => [0b10010, 0b10010, 0b01100, 0b10010, 0b10010],
+        b'Y' => [0b10001, 0b01010, 0b00100, 0b00100, 0b00100],
+        b'Z' => [0b11110, 0b00100, 0b01000, 0b10000, 0b11110],
+        b'!' => [0b00100, 0b00100, 0b00100, 0b00000, 0b00100],
+        b'-' => [0b00000, 0b00000, 0b01110, 0b00000, 0b00000],
+        _ => [0; 5],
+    }
+}
+
+pub fn icon(icon: Icon) -> Frame {
+    let rows = match icon {
+        Icon::Heart => [0b01010, 0b11111, 0b11111, 0b01110, 0b00100],
+        Icon::Happy => [0b00000, 0b01010, 0b00000, 0b10001, 0b01110],
+        Icon::Sad => [0b00000, 0b01010, 0b00000, 0b01110, 0b10001],
+        Icon::Skull => [0b01110, 0b10101, 0b11111, 0b01110, 0b01110],
+    };
+    from_rows(rows)
+}
+
+fn from_rows(rows: [u8; 5]) -> Frame {
+    let mut frame = BLANK;
+    for (y, row) in rows.iter().enumerate() {
+        for x in 0..5 {
+            frame[y][x] = (row >> (4 - x)) & 1;
+        }
+    }
+    frame
+}
+
+// Number of steps it takes to scroll the whole text in from the right and out to the left
+pub fn scroll_length(text: &str) -> usize {
+    5 + text.len() * GLYPH_STRIDE
+}
+
+// The 5x5 window of the scrolling text at the given step
+pub fn scroll_frame(text: &str, step: usize) -> Frame {
+    let mut frame = BLANK;
+
+    for x in 0..5 {
+        // The text starts just off the right edge of the display
+        let Some(column) = (step + x).checked_sub(5) else {
+            continue;
+        };
+        let (index, offset) = (column / GLYPH_STRIDE, column % GLYPH_STRIDE);
+        let (Some(&c), true) = (text.as_bytes().get(index), offset < 5) else {
+            continue;
+        };
+
+        let rows = glyph(c);
+        for y in 0..5 {
+            frame[y][x] = (rows[y] >> (4 - offset)) & 1;
+        }
+    }
+    frame
+}
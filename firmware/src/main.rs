@@ -0,0 +1,257 @@
+// Controller firmware for the micro:bit v2. Sends button presses, shakes and
+// accelerometer readings to the game using the protocol in `microbit-protocol`,
+// and shows the icons and scores the game sends back on the LED matrix.
+
+#![no_std]
+#![no_main]
+
+mod font;
+
+use core::cell::RefCell;
+
+use cortex_m::interrupt::{free, Mutex};
+use cortex_m_rt::entry;
+use embedded_hal::digital::v2::InputPin;
+use embedded_hal::serial::{Read as _, Write as _};
+use embedded_hal::timer::CountDown;
+use heapless::String;
+use lsm303agr::{AccelOutputDataRate, Lsm303agr};
+use microbit::{
+    board::Board,
+    display::nonblocking::{BitImage, Display},
+    hal::{
+        timer::Timer,
+        twim::{self, Twim},
+        uarte::{Baudrate, Parity, Uarte, UarteRx, UarteTx},
+    },
+    pac::{self, interrupt, TIMER1, UARTE0},
+};
+use microbit_protocol::{
+    self as protocol, Button, Command, Gesture, Icon, Message, PROTOCOL_VERSION,
+};
+use panic_halt as _;
+
+use crate::font::Frame;
+
+// The main loop runs on a 10 ms tick
+const TICK_US: u32 = 10_000;
+const ACCEL_EVERY_TICKS: u32 = 5;
+const ICON_TICKS: u32 = 150;
+const SCROLL_STEP_TICKS: u32 = 12;
+// Total acceleration (in milli-g) that counts as a shake, and how long to wait before the next one
+const SHAKE_THRESHOLD_MG: i32 = 1800;
+const SHAKE_COOLDOWN_TICKS: u32 = 50;
+const LINE_CAPACITY: usize = 64;
+const SCROLL_CAPACITY: usize = 32;
+
+static DISPLAY: Mutex<RefCell<Option<Display<TIMER1>>>> = Mutex::new(RefCell::new(None));
+
+#[interrupt]
+fn TIMER1() {
+    free(|cs| {
+        if let Some(display) = DISPLAY.borrow(cs).borrow_mut().as_mut() {
+            display.handle_display_event();
+        }
+    });
+}
+
+fn show(frame: &Frame) {
+    free(|cs| {
+        if let Some(display) = DISPLAY.borrow(cs).borrow_mut().as_mut() {
+            display.show(&BitImage::new(frame));
+        }
+    });
+}
+
+struct Serial {
+    tx: UarteTx<UARTE0>,
+    rx: UarteRx<UARTE0>,
+}
+
+impl Serial {
+    fn send(&mut self, message: Message) {
+        let mut line: String<LINE_CAPACITY> = String::new();
+        if protocol::write_line(&mut line, message).is_err() || line.push('\n').is_err() {
+            return;
+        }
+
+        for byte in line.bytes() {
+            let _ = nb::block!(self.tx.write(byte));
+        }
+        let _ = nb::block!(self.tx.flush());
+    }
+}
+
+enum Screen {
+    Idle,
+    Icon { until: u32 },
+    Scroll { text: String<SCROLL_CAPACITY>, step: usize, next_step: u32 },
+}
+
+impl Screen {
+    fn scroll(text: &str, ticks: u32) -> Self {
+        let mut buffer = String::new();
+        for c in text.chars() {
+            if buffer.push(c).is_err() {
+                break;
+            }
+        }
+        Screen::Scroll { text: buffer, step: 0, next_step: ticks }
+    }
+
+    fn update(&mut self, ticks: u32) {
+        match self {
+            Screen::Idle => {}
+            Screen::Icon { until } => {
+                if ticks >= *until {
+                    show(&font::BLANK);
+                    *self = Screen::Idle;
+                }
+            }
+            Screen::Scroll { text, step, next_step } => {
+                if ticks < *next_step {
+                    return;
+                }
+                if *step > font::scroll_length(text) {
+                    show(&font::BLANK);
+                    *self = Screen::Idle;
+                    return;
+                }
+
+                show(&font::scroll_frame(text, *step));
+                *step += 1;
+                *next_step = ticks + SCROLL_STEP_TICKS;
+            }
+        }
+    }
+}
+
+// Reports button changes, the 10 ms tick is enough debouncing
+struct ButtonTracker {
+    button: Button,
+    pressed: bool,
+}
+
+impl ButtonTracker {
+    fn new(button: Button) -> Self {
+        Self { button, pressed: false }
+    }
+
+    fn update(&mut self, pressed: bool) -> Option<Message> {
+        if pressed == self.pressed {
+            return None;
+        }
+        self.pressed = pressed;
+        Some(Message::Button { button: self.button, pressed })
+    }
+}
+
+fn handle_command(line: &str, serial: &mut Serial, screen: &mut Screen, ticks: u32) {
+    let Ok(command) = protocol::parse_command(line) else {
+        return;
+    };
+
+    match command {
+        // The game opened the port, so tell it who we are
+        Command::Hello { .. } => {
+            serial.send(Message::Hello { version: PROTOCOL_VERSION });
+            show(&font::icon(Icon::Happy));
+            *screen = Screen::Icon { until: ticks + ICON_TICKS };
+        }
+        Command::Icon(icon) => {
+            show(&font::icon(icon));
+            *screen = Screen::Icon { until: ticks + ICON_TICKS };
+        }
+        Command::Score(score) => {
+            let mut text: String<SCROLL_CAPACITY> = String::new();
+            let _ = core::fmt::write(&mut text, format_args!("{}", score));
+            *screen = Screen::scroll(&text, ticks);
+        }
+        Command::Scroll(text) => *screen = Screen::scroll(text, ticks),
+        Command::Clear => {
+            show(&font::BLANK);
+            *screen = Screen::Idle;
+        }
+    }
+}
+
+#[entry]
+fn main() -> ! {
+    let board = Board::take().unwrap();
+
+    let display = Display::new(board.TIMER1, board.display_pins);
+    free(|cs| DISPLAY.borrow(cs).replace(Some(display)));
+    unsafe { pac::NVIC::unmask(pac::Interrupt::TIMER1) };
+
+    let uarte = Uarte::new(
+        board.UARTE0,
+        board.uart.into(),
+        Parity::EXCLUDED,
+        Baudrate::BAUD115200,
+    );
+    let tx_buffer = cortex_m::singleton!(: [u8; LINE_CAPACITY] = [0; LINE_CAPACITY]).unwrap();
+    let rx_buffer = cortex_m::singleton!(: [u8; 1] = [0; 1]).unwrap();
+    let (tx, rx) = uarte.split(tx_buffer, rx_buffer).unwrap();
+    let mut serial = Serial { tx, rx };
+
+    let i2c = Twim::new(board.TWIM0, board.i2c_internal.into(), twim::Frequency::K100);
+    let mut sensor = Lsm303agr::new_with_i2c(i2c);
+    sensor.init().unwrap();
+    sensor.set_accel_odr(AccelOutputDataRate::Hz50).unwrap();
+
+    let button_a = board.buttons.button_a;
+    let button_b = board.buttons.button_b;
+    let mut tracker_a = ButtonTracker::new(Button::A);
+    let mut tracker_b = ButtonTracker::new(Button::B);
+
+    let mut timer = Timer::new(board.TIMER0);
+    timer.start(TICK_US);
+    let mut ticks: u32 = 0;
+    let mut next_shake: u32 = 0;
+    let mut screen = Screen::Idle;
+    let mut line: String<LINE_CAPACITY> = String::new();
+
+    serial.send(Message::Hello { version: PROTOCOL_VERSION });
+
+    loop {
+        // Read as often as possible, the UART only buffers a few bytes
+        while let Ok(byte) = serial.rx.read() {
+            if byte == b'\n' {
+                handle_command(&line, &mut serial, &mut screen, ticks);
+                line.clear();
+            } else if line.push(byte as char).is_err() {
+                // Too long to be a valid line, drop it
+                line.clear();
+            }
+        }
+
+        if timer.wait().is_err() {
+            continue;
+        }
+        timer.start(TICK_US);
+        ticks = ticks.wrapping_add(1);
+
+        // Buttons are active low
+        if let Some(message) = tracker_a.update(button_a.is_low().unwrap_or(false)) {
+            serial.send(message);
+        }
+        if let Some(message) = tracker_b.update(button_b.is_low().unwrap_or(false)) {
+            serial.send(message);
+        }
+
+        if ticks % ACCEL_EVERY_TICKS == 0 {
+            if let Ok(data) = sensor.accel_data() {
+                let clamp = |value: i32| value.clamp(i16::MIN as i32, i16::MAX as i32) as i16;
+                serial.send(Message::Accel { x: clamp(data.x), y: clamp(data.y), z: clamp(data.z) });
+
+                let magnitude_squared = data.x * data.x + data.y * data.y + data.z * data.z;
+                if magnitude_squared > SHAKE_THRESHOLD_MG * SHAKE_THRESHOLD_MG && ticks >= next_shake {
+                    serial.send(Message::Gesture(Gesture::Shake));
+                    next_shake = ticks + SHAKE_COOLDOWN_TICKS;
+                }
+            }
+        }
+
+        screen.update(ticks);
+    }
+}
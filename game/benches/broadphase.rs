@@ -0,0 +1,86 @@
+// Compares the old loop over every collider with the spatial grid broadphase.
+// Run with `cargo bench --bench broadphase`.
+
+use bevy::prelude::*;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use jump_and_run_platform::collision::aabb_overlap;
+use jump_and_run_platform::spatial_grid::SpatialGrid;
+use jump_and_run_platform::world_grid::TILE_SIZE;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+const PLAYER_SIZE: Vec2 = Vec2::new(8., 1.);
+const OBSTACLE_SIZE: Vec2 = Vec2::new(16., 5.);
+// Roughly one frame of scrolling at high speed
+const SCROLL_STEP: f32 = 3.0;
+
+struct Scene {
+    entities: Vec<Entity>,
+    positions: Vec<Vec2>,
+    player: Vec2,
+}
+
+// Obstacles spread along a road 15 tiles wide, as long as it needs to be for the count
+fn scene(count: usize) -> Scene {
+    let mut rng = StdRng::seed_from_u64(7);
+    let width = 15.0 * TILE_SIZE;
+    let length = (count as f32 / 4.0).max(1.0) * TILE_SIZE;
+
+    let positions = (0..count)
+        .map(|_| Vec2::new(rng.gen_range(-width / 2.0..width / 2.0), rng.gen_range(-length / 2.0..length / 2.0)))
+        .collect();
+    let entities = (0..count as u32).map(Entity::from_raw).collect();
+
+    Scene { entities, positions, player: Vec2::ZERO }
+}
+
+fn brute_force(scene: &Scene) -> usize {
+    scene.positions.iter()
+        .filter(|&&position| aabb_overlap(scene.player, PLAYER_SIZE, position, OBSTACLE_SIZE))
+        .count()
+}
+
+fn with_grid(scene: &Scene, grid: &SpatialGrid) -> usize {
+    grid.query(scene.player, PLAYER_SIZE)
+        .into_iter()
+        .filter(|entity| aabb_overlap(scene.player, PLAYER_SIZE, scene.positions[entity.index() as usize], OBSTACLE_SIZE))
+        .count()
+}
+
+fn scroll(scene: &mut Scene, grid: &mut SpatialGrid) {
+    for (entity, position) in scene.entities.iter().zip(scene.positions.iter_mut()) {
+        position.y -= SCROLL_STEP;
+        grid.insert(*entity, *position, OBSTACLE_SIZE);
+    }
+}
+
+fn broadphase(c: &mut Criterion) {
+    let mut group = c.benchmark_group("player_vs_colliders");
+
+    for count in [1_000, 5_000, 20_000] {
+        let mut scene = scene(count);
+        let mut grid = SpatialGrid::default();
+        for (entity, position) in scene.entities.iter().zip(&scene.positions) {
+            grid.insert(*entity, *position, OBSTACLE_SIZE);
+        }
+        assert_eq!(brute_force(&scene), with_grid(&scene, &grid));
+
+        group.bench_with_input(BenchmarkId::new("brute_force", count), &scene, |b, scene| {
+            b.iter(|| black_box(brute_force(scene)))
+        });
+        group.bench_with_input(BenchmarkId::new("grid_query", count), &grid, |b, grid| {
+            b.iter(|| black_box(with_grid(&scene, grid)))
+        });
+        // A whole frame: everything scrolls, moves cells where needed, then the player looks around
+        group.bench_function(BenchmarkId::new("grid_scroll_and_query", count), |b| {
+            b.iter(|| {
+                scroll(&mut scene, &mut grid);
+                black_box(with_grid(&scene, &grid))
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, broadphase);
+criterion_main!(benches);
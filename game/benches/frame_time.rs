@@ -0,0 +1,125 @@
+// Frame time of the scrolling world deep into a long run, with rows of tiles
+// recycled through the EntityPool against despawning them and spawning new ones.
+// Run with `cargo bench --bench frame_time`.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use jump_and_run_platform::pool::{recycle_grid_objects, EntityPool, RECYCLE_Y};
+use jump_and_run_platform::spatial_grid::SpatialGrid;
+use jump_and_run_platform::world_grid::{
+    move_grid_objects, GridConfig, GridObject, GRID_SIZE_X, GRID_SIZE_Y, MAX_SCROLL_SPEED, TILE_SIZE,
+};
+
+const FRAME: Duration = Duration::from_nanos(16_666_667);
+// Played before measuring, ten minutes at 60 fps
+const WARM_UP_FRAMES: u32 = 36_000;
+const POOL_KEY: &str = "tile";
+
+#[derive(Resource, Clone, Copy, Debug)]
+enum Recycling {
+    Despawn,
+    Pooled,
+}
+
+// The next row of tiles to spawn, counted from the bottom of the screen at the start
+#[derive(Resource, Default)]
+struct NextRow(u32);
+
+// Fills the screen with rows of tiles from the top, like the old background spawner
+fn spawn_rows(
+    mut commands: Commands,
+    recycling: Res<Recycling>,
+    grid_config: Res<GridConfig>,
+    mut pool: ResMut<EntityPool>,
+    mut next_row: ResMut<NextRow>,
+) {
+    let half_height = GRID_SIZE_Y as f32 * TILE_SIZE / 2.0;
+
+    loop {
+        let y = -half_height + next_row.0 as f32 * TILE_SIZE - grid_config.scroll_px();
+        if y > half_height + TILE_SIZE {
+            break;
+        }
+
+        for column in 0..GRID_SIZE_X {
+            let x = (column as f32 - (GRID_SIZE_X / 2) as f32) * TILE_SIZE;
+            let tile = (
+                Sprite {
+                    color: Color::srgb(0.3, 0.6, 0.3),
+                    custom_size: Some(Vec2::splat(TILE_SIZE)),
+                    ..default()
+                },
+                Transform::from_xyz(x, y, 0.0),
+                GridObject,
+            );
+
+            match *recycling {
+                Recycling::Despawn => {
+                    commands.spawn(tile);
+                }
+                Recycling::Pooled => {
+                    pool.take(&mut commands, POOL_KEY).insert(tile);
+                }
+            }
+        }
+        next_row.0 += 1;
+    }
+}
+
+fn despawn_offscreen(mut commands: Commands, query: Query<(Entity, &Transform), With<GridObject>>) {
+    for (entity, transform) in query.iter() {
+        if transform.translation.y < RECYCLE_Y {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+fn long_run(recycling: Recycling) -> App {
+    let mut app = App::new();
+    app.insert_resource(recycling)
+        .init_resource::<Time>()
+        .init_resource::<SpatialGrid>()
+        .init_resource::<EntityPool>()
+        .init_resource::<NextRow>()
+        .insert_resource(GridConfig {
+            tile_size: TILE_SIZE,
+            grid_width: GRID_SIZE_X,
+            grid_height: GRID_SIZE_Y,
+            scroll_speed: MAX_SCROLL_SPEED,
+            frame_scroll: 0.0,
+            distance_moved: 0.0,
+        });
+
+    match recycling {
+        Recycling::Despawn => app.add_systems(Update, (move_grid_objects, despawn_offscreen, spawn_rows).chain()),
+        Recycling::Pooled => app.add_systems(Update, (move_grid_objects, recycle_grid_objects, spawn_rows).chain()),
+    };
+
+    for _ in 0..WARM_UP_FRAMES {
+        step(&mut app);
+    }
+    app
+}
+
+fn step(app: &mut App) {
+    app.world_mut().resource_mut::<Time>().advance_by(FRAME);
+    app.update();
+}
+
+fn frame_time(c: &mut Criterion) {
+    let mut group = c.benchmark_group("frame_time");
+
+    for recycling in [Recycling::Despawn, Recycling::Pooled] {
+        let mut app = long_run(recycling);
+        group.bench_function(BenchmarkId::from_parameter(format!("{:?}", recycling)), |b| {
+            b.iter(|| step(&mut app))
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, frame_time);
+criterion_main!(benches);
@@ -0,0 +1,150 @@
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+use bevy::render::{
+    camera::RenderTarget,
+    render_resource::{
+        Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+    },
+    view::RenderLayers,
+};
+use bevy::window::WindowResized;
+use crate::moving_road::column_x;
+use crate::world_chunks::{ChunkSpawner, CHUNK_ROWS};
+use crate::world_grid::{GridObject, GRID_SIZE_X, GRID_Z, TILE_SIZE};
+
+// Spil opløsning - justér efter behov
+const RES_WIDTH: u32 = 480;  // 15 * 32
+const RES_HEIGHT: u32 = 480; // 15 * 32
+
+// Render layers
+pub const GAME_LAYERS: RenderLayers = RenderLayers::layer(0);
+pub const UI_LAYERS: RenderLayers = RenderLayers::layer(1);
+
+#[derive(Component)]
+pub struct TileGrid {
+    pub x: u32,
+    pub y: u32,
+    pub occupied: bool,
+}
+
+#[derive(Component)]
+pub struct Log;
+
+#[derive(Component)]
+pub struct GameCanvas;
+
+#[derive(Component)]
+pub struct GameCamera;
+
+#[derive(Component)]
+pub struct OuterCamera;
+
+// Opsætter kamera og kanvas for pixel-perfect rendering
+pub fn setup_pixel_grid(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let canvas_size = Extent3d {
+        width: RES_WIDTH,
+        height: RES_HEIGHT,
+        ..default()
+    };
+
+    // Opret kanvas med fast opløsning
+    let mut canvas = Image {
+        texture_descriptor: TextureDescriptor {
+            label: None,
+            size: canvas_size,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Bgra8UnormSrgb,
+            mip_level_count: 1,
+            sample_count: 1,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_DST
+                | TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        },
+        ..default()
+    };
+
+    canvas.resize(canvas_size);
+    let image_handle = images.add(canvas);
+
+    // Indre kamera der renderer spillet til kanvas
+    commands.spawn((
+        Camera2d,
+        Camera {
+            order: -1,
+            target: RenderTarget::Image(image_handle.clone().into()),
+            clear_color: ClearColorConfig::Custom(Color::srgb(0.1, 0.1, 0.1)),
+            ..default()
+        },
+        Msaa::Off,
+        GameCamera,
+        GAME_LAYERS,
+    ));
+
+    // Kanvas sprite
+    commands.spawn((
+        Sprite {
+            image: image_handle,
+            ..default()
+        },
+        Transform::default(),
+        GameCanvas,
+        UI_LAYERS,
+    ));
+
+    // Ydre kamera der renderer kanvas til skærmen
+    commands.spawn((
+        Camera2d, 
+        Msaa::Off, 
+        OuterCamera, 
+        UI_LAYERS
+    ));
+}
+
+const POOL_KEY: &str = "terrain";
+
+// Terrain stage of the chunk pipeline, a background tile in every cell of the chunk
+pub fn generate_terrain(spawner: &mut ChunkSpawner) {
+    for row in 0..CHUNK_ROWS {
+        let y = spawner.chunk.row_y(row);
+
+        for column in 0..GRID_SIZE_X {
+            let image = spawner.asset_server.load("tileset/background.png");
+            spawner.spawn(POOL_KEY, (
+                Sprite {
+                    image,
+                    custom_size: Some(Vec2::new(TILE_SIZE, TILE_SIZE)),
+                    anchor: Anchor::Center,
+                    ..default()
+                },
+                Transform::from_xyz(column_x(column), y, GRID_Z),
+                TileGrid {
+                    x: column,
+                    y: row,
+                    occupied: false,
+                },
+                GridObject,
+                GAME_LAYERS,
+            ));
+        }
+    }
+}
+
+// Make sure this function is public
+pub fn fit_canvas(
+    mut resize_events: EventReader<WindowResized>,
+    mut query: Query<&mut OrthographicProjection, With<OuterCamera>>,
+) {
+    for event in resize_events.read() {
+        if let Ok(mut projection) = query.get_single_mut() {
+            let h_scale = event.width / RES_WIDTH as f32;
+            let v_scale = event.height / RES_HEIGHT as f32;
+
+            let zoom_factor = 3.5;
+            projection.scale = 1. / (h_scale.min(v_scale).floor().max(1.0) * zoom_factor);
+        }
+    }
+}
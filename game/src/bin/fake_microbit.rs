@@ -0,0 +1,50 @@
+// Plays a script as if it came from a micro:bit:
+//   cargo run --bin fake_microbit -- game/scripts/demo.txt
+// then start the game with the printed port.
+
+#[cfg(unix)]
+fn main() {
+    use std::time::Duration;
+    use jump_and_run_platform::fake_microbit::{load_script, FakeMicrobit};
+
+    let Some(path) = std::env::args().nth(1) else {
+        eprintln!("Usage: fake_microbit <script> [--loop]");
+        std::process::exit(2);
+    };
+    let repeat = std::env::args().any(|arg| arg == "--loop");
+
+    let steps = load_script(&path).unwrap_or_else(|err| {
+        eprintln!("⚠️ {}", err);
+        std::process::exit(1);
+    });
+    let mut board = FakeMicrobit::open().expect("could not open a pseudo-terminal");
+
+    println!("Fake micro:bit on {}", board.port_name());
+    println!("Start the game with: cargo run -- --serial-port {}", board.port_name());
+
+    loop {
+        if let Err(err) = board.wait_for_game(Duration::from_secs(3600)) {
+            eprintln!("⚠️ {}", err);
+            std::process::exit(1);
+        }
+        println!("Game connected, playing {}", path);
+
+        if let Err(err) = board.play(&steps) {
+            eprintln!("⚠️ {}", err);
+        }
+
+        // Show whatever the game sends back until it goes quiet
+        while let Ok(Some(line)) = board.read_line(Duration::from_secs(2)) {
+            println!("Game: {}", line);
+        }
+
+        if !repeat {
+            break;
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn main() {
+    eprintln!("fake_microbit needs pseudo-terminals and only runs on Linux and macOS");
+}
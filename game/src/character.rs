@@ -0,0 +1,494 @@
+use bevy::asset::{AssetServer, Assets};
+use bevy::math::{UVec2, Vec3};
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+use crate::collision::{Collider, LAYER_OBSTACLE, LAYER_PICKUP, LAYER_PLAYER, LAYER_ZONE};
+use crate::daily::GameMode;
+use crate::health::{Lives, LivesConfig};
+use crate::input::{Action, ActionState};
+use crate::microbit::{BoardMessage, SerialOutbox, TiltSteering};
+use crate::moving_road::{Lane, SegmentId};
+use microbit_protocol::Icon;
+use crate::world_grid::{GridConfig, PLAYER_Z};
+use std::time::Duration;
+use crate::scoreboard::{ScoreText, ScoreDisplay, ScoreboardState, HighScores, FinalScore, GameState};
+
+#[derive(Component)]
+pub struct AnimationIndices {
+    pub first: usize,
+    pub last: usize,
+}
+
+#[derive(Component, Deref, DerefMut)]
+pub struct AnimationTimer(pub Timer);
+
+#[derive(Component, Default)]
+pub struct Player {
+    // True while the player is in the air
+    pub is_jumping: bool,
+    pub is_ducking: bool,
+    pub is_dead: bool,
+    pub final_score: f32,
+    // Simulated height above the road, the transform stays on the ground
+    pub height: f32,
+    pub vertical_velocity: f32,
+    // Time left to still jump after leaving the ground
+    pub coyote_timer: f32,
+    // Time left for a jump pressed just before landing
+    pub jump_buffer: f32,
+    // One-shot jumps (micro:bit) can't be held, so they always get the full height
+    pub buffered_full_jump: bool,
+    pub full_jump: bool,
+    // Which branch of a fork the player is on
+    pub lane: Lane,
+    // The segment of road graph under the player
+    pub segment: Option<SegmentId>,
+}
+
+impl Player {
+    // Advances the jump by one frame and returns the animation to switch to, if any
+    pub fn update_jump(&mut self, delta: f32, just_pressed: bool, pulsed: bool, held: bool) -> Option<AnimationState> {
+        let mut change = None;
+
+        if just_pressed {
+            self.jump_buffer = JUMP_BUFFER_TIME;
+            self.buffered_full_jump = pulsed;
+        } else {
+            self.jump_buffer = (self.jump_buffer - delta).max(0.);
+        }
+
+        if self.is_jumping {
+            let holding = self.full_jump || held;
+            let gravity = if holding && self.vertical_velocity > 0. {
+                GRAVITY * HELD_GRAVITY_SCALE
+            } else {
+                GRAVITY
+            };
+
+            self.vertical_velocity -= gravity * delta;
+            self.height += self.vertical_velocity * delta;
+
+            if self.height <= 0. {
+                self.height = 0.;
+                self.vertical_velocity = 0.;
+                self.is_jumping = false;
+                change = Some(AnimationState::Run);
+            }
+        }
+
+        if self.is_jumping {
+            self.coyote_timer = (self.coyote_timer - delta).max(0.);
+        } else {
+            self.coyote_timer = COYOTE_TIME;
+        }
+
+        // Landing this frame still counts, so a buffered jump goes off right away
+        if self.jump_buffer > 0. && self.coyote_timer > 0. {
+            self.jump_buffer = 0.;
+            self.coyote_timer = 0.;
+            self.full_jump = self.buffered_full_jump;
+            self.vertical_velocity = JUMP_VELOCITY;
+            self.is_jumping = true;
+            change = Some(AnimationState::Jump);
+        }
+
+        change
+    }
+}
+
+#[derive(Component)]
+pub struct PlayerShadow;
+
+pub const PLAYER_HITBOX_SIZE: Vec2 = Vec2::new(8., 1.);
+const PLAYER_SPRITE_SIZE: f32 = 32.0;
+// Where the feet are, relative to the middle of the sprite
+const SHADOW_OFFSET: f32 = -12.0;
+const SHADOW_SIZE: Vec2 = Vec2::new(14., 5.);
+
+// Jump tuning, in pixels and seconds
+const JUMP_VELOCITY: f32 = 200.0;
+const GRAVITY: f32 = 900.0;
+// Gravity while the jump button is held on the way up, holding longer jumps higher
+const HELD_GRAVITY_SCALE: f32 = 0.4;
+// How much of the sprite's height is left while ducking
+const DUCK_SQUASH: f32 = 0.6;
+// Time in the air for the shortest jump, the soonest the player can jump again
+pub const MIN_JUMP_AIRTIME: f32 = 2.0 * JUMP_VELOCITY / GRAVITY;
+const COYOTE_TIME: f32 = 0.1;
+const JUMP_BUFFER_TIME: f32 = 0.12;
+const ANIMATION_SPEED: f32 = 0.1;
+
+#[derive(Component)]
+pub struct AnimationConfig {
+    pub run_texture: Handle<Image>,
+    pub run_layout: Handle<TextureAtlasLayout>,
+    pub run_indices: (usize, usize),
+
+    pub jump_texture: Handle<Image>,
+    pub jump_layout: Handle<TextureAtlasLayout>,
+    pub jump_indices: (usize, usize),
+
+    pub fps: u8,
+    pub frame_timer: Timer,
+    pub state: AnimationState,
+}
+
+
+
+
+
+#[derive(Component, Debug, PartialEq, Eq)]
+pub enum AnimationState {
+    Run,
+    Jump,
+}
+
+
+impl AnimationConfig {
+    pub fn new(
+        run_texture: Handle<Image>,
+        run_layout: Handle<TextureAtlasLayout>,
+        run_indices: (usize, usize),
+        jump_texture: Handle<Image>,
+        jump_layout: Handle<TextureAtlasLayout>,
+        jump_indices: (usize, usize),
+        fps: u8,
+    ) -> Self {
+        Self {
+            run_texture,
+            run_layout,
+            run_indices,
+            jump_texture,
+            jump_layout,
+            jump_indices,
+            fps,
+            frame_timer: timer_from_fps(fps),
+            state: AnimationState::Run,
+        }
+    }
+}
+
+
+pub fn timer_from_fps(fps: u8) -> Timer {
+    Timer::new(Duration::from_secs_f32(1.0 / (fps as f32)), TimerMode::Repeating)
+}
+
+
+pub fn animate_sprite(time: Res<Time>, mut query: Query<(&AnimationIndices, &mut AnimationTimer, &mut Sprite)>,
+) {
+    for(indices, mut timer, mut sprite) in &mut query {
+        timer.tick(time.delta());
+
+        if timer.just_finished() {
+            if let Some(atlas) = &mut sprite.texture_atlas {
+                atlas.index = if atlas.index == indices.last {
+                    indices.first
+                } else {
+                    atlas.index + 1
+                }
+            }
+        }
+    }
+}
+
+pub fn setup_character(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+    lives: Res<LivesConfig>,
+) {
+    let run_texture = asset_server.load("textures/character/run.png");
+    let jump_texture = asset_server.load("textures/character/jump.png");
+
+    let run_layout = texture_atlas_layouts.add(TextureAtlasLayout::from_grid(UVec2::splat(32), 6, 1, None, None));
+    let jump_layout = texture_atlas_layouts.add(TextureAtlasLayout::from_grid(UVec2::splat(32), 3, 1, None, None));
+
+    let animation_config = AnimationConfig::new(
+        run_texture.clone(),
+        run_layout.clone(),
+        (0, 5),
+        jump_texture.clone(),
+        jump_layout.clone(),
+        (0, 2),
+        10,
+    );
+
+
+    commands.spawn((
+        Player::default(),
+        Sprite {
+            image: run_texture,
+            texture_atlas: Some(TextureAtlas {
+                layout: run_layout,
+                index: 0,
+            }),
+            ..default()
+        },
+        Transform::from_xyz(0., 0., PLAYER_Z),
+        animation_config,
+        Lives { remaining: lives.0 },
+        Collider::solid(PLAYER_HITBOX_SIZE, LAYER_PLAYER, LAYER_OBSTACLE | LAYER_PICKUP | LAYER_ZONE),
+    )).with_children(|parent| {
+        parent.spawn((
+            Sprite {
+                color: Color::srgba(0.0, 0.0, 0.0, 0.35),
+                custom_size: Some(SHADOW_SIZE),
+                ..default()
+            },
+            Transform::from_xyz(0., SHADOW_OFFSET, -0.1),
+            PlayerShadow,
+        ));
+    });
+}
+
+
+pub const PLAYER_SPEED: f32 = 50.0;
+// How far the player can move from the middle of the road
+pub const SCREEN_BOUND: f32 = 220.0;
+// How far a single Left/Right from the micro:bit moves the player
+const CONTROLLER_STEP: f32 = 16.0;
+
+pub fn move_character_horizontal(
+    mut query: Query<(&mut Transform, &Player)>,
+    time: Res<Time>,
+    actions: Res<ActionState>,
+    tilt: Res<TiltSteering>,
+) {
+    // A one-shot press (from the micro:bit) moves a fixed step instead of a held speed
+    let mut controller_offset = 0.;
+    if actions.pulsed(Action::MoveLeft) {
+        controller_offset -= CONTROLLER_STEP;
+    }
+    if actions.pulsed(Action::MoveRight) {
+        controller_offset += CONTROLLER_STEP;
+    }
+
+    for (mut transform, player) in query.iter_mut() {
+        if player.is_dead {
+            return;
+        }
+
+        let mut direction = 0.;
+
+        if actions.pressed(Action::MoveLeft) && !actions.pulsed(Action::MoveLeft) {
+            direction -= 1.;
+        }
+
+        if actions.pressed(Action::MoveRight) && !actions.pulsed(Action::MoveRight) {
+            direction += 1.;
+        }
+
+        // Held buttons win, then the gamepad stick, otherwise speed follows how far the micro:bit is tilted
+        if direction == 0. {
+            direction = actions.move_axis();
+        }
+        if direction == 0. {
+            direction = tilt.axis();
+        }
+
+        let move_delta = direction * PLAYER_SPEED * time.delta_secs();
+
+        transform.translation.x += move_delta + controller_offset;
+        transform.translation.x = transform.translation.x.clamp(-SCREEN_BOUND, SCREEN_BOUND);
+    }
+}
+
+
+pub fn jump(
+    time: Res<Time>,
+    mut player_query: Query<(&mut Player, &mut AnimationConfig, &mut Sprite)>,
+    actions: Res<ActionState>,
+) {
+    let delta = time.delta_secs();
+
+    for (mut player, mut animation, mut sprite) in &mut player_query {
+        if player.is_dead {
+            continue;
+        }
+
+        let change = player.update_jump(
+            delta,
+            actions.just_pressed(Action::Jump),
+            actions.pulsed(Action::Jump),
+            actions.pressed(Action::Jump),
+        );
+
+        match change {
+            Some(AnimationState::Run) => {
+                animation.state = AnimationState::Run;
+
+                // Skift tilbage til run sprite-sheet
+                sprite.image = animation.run_texture.clone();
+                sprite.texture_atlas = Some(TextureAtlas {
+                    layout: animation.run_layout.clone(),
+                    index: animation.run_indices.0,
+                });
+            }
+            Some(AnimationState::Jump) => {
+                animation.state = AnimationState::Jump;
+
+                // Skift til jump sprite-sheet
+                sprite.image = animation.jump_texture.clone();
+                sprite.texture_atlas = Some(TextureAtlas {
+                    layout: animation.jump_layout.clone(),
+                    index: animation.jump_indices.0,
+                });
+            }
+            None => {}
+        }
+
+        // Only on the ground, ducking in the air does nothing
+        player.is_ducking = !player.is_jumping && actions.pressed(Action::Duck);
+        let sprite_height = if player.is_ducking {
+            PLAYER_SPRITE_SIZE * DUCK_SQUASH
+        } else {
+            PLAYER_SPRITE_SIZE
+        };
+        sprite.custom_size = Some(Vec2::new(PLAYER_SPRITE_SIZE, sprite_height));
+
+        // Draw the sprite above its ground position instead of moving the transform.
+        // A squashed sprite keeps its feet where they were.
+        let feet_lift = (PLAYER_SPRITE_SIZE - sprite_height) / 2.;
+        sprite.anchor = Anchor::Custom(Vec2::new(0., -(player.height - feet_lift) / sprite_height));
+    }
+}
+
+// The shadow stays on the ground and shrinks the higher the player is
+pub fn update_player_shadow(
+    player_query: Query<&Player>,
+    mut shadow_query: Query<(&Parent, &mut Transform), With<PlayerShadow>>,
+) {
+    for (parent, mut transform) in shadow_query.iter_mut() {
+        if let Ok(player) = player_query.get(parent.get()) {
+            let scale = (1. - player.height / 80.).clamp(0.5, 1.);
+            transform.scale = Vec3::new(scale, scale, 1.);
+        }
+    }
+}
+
+
+pub fn execute_animations(time: Res<Time>, mut query: Query<(&mut AnimationConfig, &mut Sprite)>) {
+    for (mut config, mut sprite) in &mut query {
+        config.frame_timer.tick(time.delta());
+
+        if config.frame_timer.just_finished() {
+            let (first, last) = match config.state {
+                AnimationState::Run => config.run_indices,
+                AnimationState::Jump => config.jump_indices,
+            };
+
+            if let Some(atlas) = &mut sprite.texture_atlas {
+                if atlas.index >= last {
+                    atlas.index = first;
+                } else {
+                    atlas.index += 1;
+                }
+            }
+        }
+    }
+}
+
+pub fn handle_player_death(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Player)>,
+    mut grid_config: ResMut<GridConfig>,
+    score: Res<ScoreText>,
+    score_display_query: Query<Entity, With<ScoreDisplay>>,
+    mut scoreboard_state: ResMut<ScoreboardState>,
+    mut final_score: ResMut<FinalScore>,
+    high_scores: Res<HighScores>,
+    mode: Res<GameMode>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+    outbox: Res<SerialOutbox>,
+) {
+    for (entity, mut player) in query.iter_mut() {
+        if player.is_dead {
+            
+            player.final_score = score.score;
+            final_score.0 = score.score;
+            
+            if let Ok(score_entity) = score_display_query.get_single() {
+                 commands.entity(score_entity).despawn();
+            }
+
+            if high_scores.is_high_score_for(&mode, score.score) {
+                *scoreboard_state = ScoreboardState::EnterName;
+                outbox.send(BoardMessage::Icon(Icon::Heart));
+                outbox.send(BoardMessage::Scroll(format!("HIGH SCORE {}", score.score)));
+            } else {
+                *scoreboard_state = ScoreboardState::ShowScores;
+                outbox.send(BoardMessage::Icon(Icon::Sad));
+            }
+
+            grid_config.scroll_speed = 0.;
+            commands.entity(entity).despawn_recursive();
+            next_game_state.set(GameState::GameOver);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME: f32 = 1.0 / 60.0;
+
+    fn airborne_player() -> Player {
+        Player {
+            is_jumping: true,
+            height: 100.,
+            coyote_timer: COYOTE_TIME,
+            ..default()
+        }
+    }
+
+    #[test]
+    fn jump_within_the_coyote_window_after_leaving_the_ground() {
+        let mut player = airborne_player();
+        player.update_jump(COYOTE_TIME / 2., false, false, false);
+        assert!(player.is_jumping);
+
+        let change = player.update_jump(FRAME, true, false, true);
+        assert_eq!(change, Some(AnimationState::Jump));
+        assert_eq!(player.vertical_velocity, JUMP_VELOCITY);
+    }
+
+    #[test]
+    fn no_jump_once_the_coyote_window_has_passed() {
+        let mut player = airborne_player();
+        player.update_jump(COYOTE_TIME + FRAME, false, false, false);
+
+        let change = player.update_jump(FRAME, true, false, true);
+        assert_eq!(change, None);
+        assert!(player.vertical_velocity < 0.);
+    }
+
+    #[test]
+    fn a_jump_pressed_just_before_landing_goes_off_on_landing() {
+        let mut player = airborne_player();
+        player.coyote_timer = 0.;
+        player.height = 1.;
+        player.vertical_velocity = -60.;
+
+        // Too early to jump, the press is buffered instead
+        assert_eq!(player.update_jump(FRAME / 4., true, false, true), None);
+        assert_eq!(player.update_jump(FRAME, false, false, true), Some(AnimationState::Jump));
+        assert_eq!(player.vertical_velocity, JUMP_VELOCITY);
+    }
+
+    #[test]
+    fn holding_jump_goes_higher_than_tapping() {
+        let peak = |held: bool| {
+            let mut player = Player::default();
+            player.update_jump(FRAME, true, false, held);
+            let mut peak: f32 = 0.;
+            while player.is_jumping {
+                player.update_jump(FRAME, false, false, held);
+                peak = peak.max(player.height);
+            }
+            peak
+        };
+
+        assert!(peak(true) > peak(false));
+    }
+}
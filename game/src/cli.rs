@@ -0,0 +1,23 @@
+use std::env;
+
+// Looks up the value of a flag given as `--flag value` or `--flag=value`
+pub fn flag_value(name: &str) -> Option<String> {
+    let mut args = env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        if arg == name {
+            return args.next();
+        }
+
+        if let Some(value) = arg.strip_prefix(name).and_then(|rest| rest.strip_prefix('=')) {
+            return Some(value.to_string());
+        }
+    }
+
+    None
+}
+
+// Whether a flag without a value, like `--daily`, was given
+pub fn has_flag(name: &str) -> bool {
+    env::args().skip(1).any(|arg| arg == name)
+}
@@ -0,0 +1,353 @@
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+use crate::character::Player;
+use crate::health::PlayerHit;
+use crate::obstacle::{HeightSpan, ObstacleKind};
+use crate::spatial_grid::SpatialGrid;
+use crate::world_grid::{GridConfig, GridObject};
+
+// Collision layers, a collider is on one or more layers and checks against the layers in its mask
+pub const LAYER_PLAYER: u32 = 1 << 0;
+pub const LAYER_OBSTACLE: u32 = 1 << 1;
+pub const LAYER_PICKUP: u32 = 1 << 2;
+pub const LAYER_ZONE: u32 = 1 << 3;
+
+#[derive(Component)]
+pub struct Collider {
+    pub size: Vec2,
+    pub layer: u32,
+    pub mask: u32,
+    // Triggers report overlaps but never hurt anyone
+    pub is_trigger: bool,
+}
+
+impl Collider {
+    pub fn solid(size: Vec2, layer: u32, mask: u32) -> Self {
+        Self { size, layer, mask, is_trigger: false }
+    }
+
+    pub fn trigger(size: Vec2, layer: u32, mask: u32) -> Self {
+        Self { size, layer, mask, is_trigger: true }
+    }
+
+    fn interacts_with(&self, other: &Collider) -> bool {
+        self.mask & other.layer != 0 || other.mask & self.layer != 0
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CollisionKind {
+    Hit,
+    Trigger,
+}
+
+// Sent once when two colliders start overlapping
+#[derive(Event, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CollisionEvent {
+    pub a: Entity,
+    pub b: Entity,
+    pub kind: CollisionKind,
+}
+
+impl CollisionEvent {
+    // The other entity, if `entity` is part of this collision
+    pub fn other(&self, entity: Entity) -> Option<Entity> {
+        if self.a == entity {
+            Some(self.b)
+        } else if self.b == entity {
+            Some(self.a)
+        } else {
+            None
+        }
+    }
+}
+
+fn height_span(player: Option<&Player>, kind: Option<&ObstacleKind>) -> Option<HeightSpan> {
+    player.map(HeightSpan::of_player).or(kind.map(|kind| kind.height_span()))
+}
+
+pub fn aabb_overlap(a_pos: Vec2, a_size: Vec2, b_pos: Vec2, b_size: Vec2) -> bool {
+    a_pos.x - a_size.x / 2.0 < b_pos.x + b_size.x / 2.0
+        && a_pos.x + a_size.x / 2.0 > b_pos.x - b_size.x / 2.0
+        && a_pos.y - a_size.y / 2.0 < b_pos.y + b_size.y / 2.0
+        && a_pos.y + a_size.y / 2.0 > b_pos.y - b_size.y / 2.0
+}
+
+// `b` moved `scroll` pixels down the screen this frame. Checks the whole path it took
+// instead of only where it ended up, so thin, fast objects can't skip over `a`.
+pub fn swept_aabb_overlap(a_pos: Vec2, a_size: Vec2, b_pos: Vec2, b_size: Vec2, scroll: f32) -> bool {
+    let swept_center = b_pos + Vec2::new(0.0, scroll / 2.0);
+    let swept_size = b_size + Vec2::new(0.0, scroll.abs());
+    aabb_overlap(a_pos, a_size, swept_center, swept_size)
+}
+
+// New colliders, and ones that moved without scrolling
+type ColliderChanged = Or<(Added<Collider>, (Changed<Transform>, Without<GridObject>))>;
+
+// Keeps the broadphase in step with colliders that come, go, or move on their own.
+// Scrolling objects are moved in the grid by `move_grid_objects`.
+pub fn sync_spatial_grid(
+    mut grid: ResMut<SpatialGrid>,
+    changed_query: Query<(Entity, &Collider, &Transform), ColliderChanged>,
+    mut removed: RemovedComponents<Collider>,
+) {
+    for entity in removed.read() {
+        grid.remove(entity);
+    }
+
+    for (entity, collider, transform) in changed_query.iter() {
+        grid.insert(entity, transform.translation.truncate(), collider.size);
+    }
+}
+
+// A collider and what decides whether it hits: who it belongs to and whether it scrolls
+type ColliderParts<'a> = (Entity, &'a Collider, &'a Transform, Option<&'a Player>, Option<&'a ObstacleKind>, Has<GridObject>);
+
+pub fn check_collision(
+    grid: Res<SpatialGrid>,
+    grid_config: Res<GridConfig>,
+    collider_query: Query<ColliderParts>,
+    mut touching: Local<HashSet<(Entity, Entity)>>,
+    mut collision_events: EventWriter<CollisionEvent>,
+) {
+    let scroll = grid_config.frame_scroll;
+    let mut still_touching = HashSet::new();
+
+    // Only colliders that look for something need to search, the rest are found by them
+    for (a, a_collider, a_transform, a_player, a_kind, a_scrolls) in collider_query.iter() {
+        if a_collider.mask == 0 {
+            continue;
+        }
+        let a_pos = a_transform.translation.truncate();
+
+        // Scrolling colliders are filed where they ended up, at the bottom of the path
+        // they took this frame. Look along that path, not just at what is here now.
+        let search_offset = if a_scrolls { scroll / 2.0 } else { -scroll / 2.0 };
+        let search_pos = a_pos + Vec2::new(0.0, search_offset);
+        let search_size = a_collider.size + Vec2::new(0.0, scroll);
+
+        for b in grid.query(search_pos, search_size) {
+            let pair = if a < b { (a, b) } else { (b, a) };
+            if b == a || still_touching.contains(&pair) {
+                continue;
+            }
+            let Ok((_, b_collider, b_transform, b_player, b_kind, b_scrolls)) = collider_query.get(b) else {
+                continue;
+            };
+            if !a_collider.interacts_with(b_collider) {
+                continue;
+            }
+
+            // Over it, under it, or a collider without a height that always counts
+            let a_span = height_span(a_player, a_kind);
+            let b_span = height_span(b_player, b_kind);
+            if let (Some(a_span), Some(b_span)) = (a_span, b_span) {
+                if !a_span.overlaps(&b_span) {
+                    continue;
+                }
+            }
+
+            // Only the one that scrolls has moved relative to the other
+            let b_pos = b_transform.translation.truncate();
+            let overlapping = match (a_scrolls, b_scrolls) {
+                (false, true) => swept_aabb_overlap(a_pos, a_collider.size, b_pos, b_collider.size, scroll),
+                (true, false) => swept_aabb_overlap(b_pos, b_collider.size, a_pos, a_collider.size, scroll),
+                _ => aabb_overlap(a_pos, a_collider.size, b_pos, b_collider.size),
+            };
+            if !overlapping {
+                continue;
+            }
+
+            still_touching.insert(pair);
+            if touching.contains(&pair) {
+                continue;
+            }
+
+            let kind = if a_collider.is_trigger || b_collider.is_trigger {
+                CollisionKind::Trigger
+            } else {
+                CollisionKind::Hit
+            };
+            collision_events.send(CollisionEvent { a: pair.0, b: pair.1, kind });
+        }
+    }
+
+    *touching = still_touching;
+}
+
+// Anything solid that touches the player hurts it
+pub fn apply_collision_hits(
+    mut collision_events: EventReader<CollisionEvent>,
+    player_query: Query<Entity, With<Player>>,
+    mut player_hits: EventWriter<PlayerHit>,
+) {
+    for event in collision_events.read() {
+        if event.kind != CollisionKind::Hit {
+            continue;
+        }
+
+        for player in player_query.iter() {
+            if let Some(by) = event.other(player) {
+                player_hits.send(PlayerHit { player, by });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::SystemId;
+    use crate::world_grid::{GRID_SIZE_X, GRID_SIZE_Y, TILE_SIZE};
+
+    const PLAYER_SIZE: Vec2 = Vec2::new(8.0, 1.0);
+    const LOG_SIZE: Vec2 = Vec2::new(16.0, 5.0);
+
+    // Scrolls a log down past a player at the origin, one frame at a time,
+    // and reports whether any frame counted as a hit
+    fn log_hits_player(speed: f32, fps: f32, swept: bool) -> bool {
+        let step = (speed / fps).ceil();
+        let mut log_y = 240.0;
+
+        while log_y > -240.0 {
+            log_y -= step;
+            let log_pos = Vec2::new(0.0, log_y);
+            let hit = if swept {
+                swept_aabb_overlap(Vec2::ZERO, PLAYER_SIZE, log_pos, LOG_SIZE, step)
+            } else {
+                aabb_overlap(Vec2::ZERO, PLAYER_SIZE, log_pos, LOG_SIZE)
+            };
+            if hit {
+                return true;
+            }
+        }
+        false
+    }
+
+    #[test]
+    fn discrete_check_tunnels_at_extreme_speed() {
+        assert!(log_hits_player(35.0, 60.0, false));
+        assert!(!log_hits_player(5_000.0, 60.0, false));
+    }
+
+    #[test]
+    fn swept_check_catches_every_speed() {
+        for speed in [35.0, 300.0, 1_000.0, 5_000.0, 20_000.0, 100_000.0] {
+            assert!(log_hits_player(speed, 60.0, true), "missed the log at {} px/s", speed);
+        }
+    }
+
+    #[test]
+    fn swept_check_does_not_hit_what_only_passed_beside() {
+        let beside = Vec2::new(20.0, -100.0);
+        assert!(!swept_aabb_overlap(Vec2::ZERO, PLAYER_SIZE, beside, LOG_SIZE, 400.0));
+    }
+
+    #[test]
+    fn swept_check_ignores_objects_that_are_still_ahead_or_already_past() {
+        // Still above the player after moving
+        assert!(!swept_aabb_overlap(Vec2::ZERO, PLAYER_SIZE, Vec2::new(0.0, 10.0), LOG_SIZE, 50.0));
+        // Already below the player before moving
+        assert!(!swept_aabb_overlap(Vec2::ZERO, PLAYER_SIZE, Vec2::new(0.0, -60.0), LOG_SIZE, 50.0));
+    }
+
+    // A world with an empty broadphase and `check_collision` registered,
+    // so it keeps who is touching whom from one run to the next
+    fn collision_world(frame_scroll: f32) -> (World, SystemId) {
+        let mut world = World::new();
+        world.init_resource::<SpatialGrid>();
+        world.init_resource::<Events<CollisionEvent>>();
+        world.insert_resource(GridConfig {
+            tile_size: TILE_SIZE,
+            grid_width: GRID_SIZE_X,
+            grid_height: GRID_SIZE_Y,
+            scroll_speed: 35.0,
+            frame_scroll,
+            distance_moved: 0.0,
+        });
+        let system = world.register_system(check_collision);
+        (world, system)
+    }
+
+    fn place(world: &mut World, entity: Entity, position: Vec2) {
+        let size = world.get::<Collider>(entity).unwrap().size;
+        world.get_mut::<Transform>(entity).unwrap().translation = position.extend(0.0);
+        world.resource_mut::<SpatialGrid>().insert(entity, position, size);
+    }
+
+    fn spawn(world: &mut World, collider: Collider, position: Vec2) -> Entity {
+        let entity = world.spawn((collider, Transform::default())).id();
+        place(world, entity, position);
+        entity
+    }
+
+    fn collisions(world: &mut World, system: SystemId) -> Vec<CollisionEvent> {
+        world.run_system(system).unwrap();
+        world.resource_mut::<Events<CollisionEvent>>().drain().collect()
+    }
+
+    fn player_collider() -> Collider {
+        Collider::solid(PLAYER_SIZE, LAYER_PLAYER, LAYER_OBSTACLE | LAYER_PICKUP)
+    }
+
+    #[test]
+    fn colliders_only_meet_the_layers_in_their_masks() {
+        let player = player_collider();
+        let log = Collider::solid(LOG_SIZE, LAYER_OBSTACLE, 0);
+        let zone = Collider::trigger(LOG_SIZE, LAYER_ZONE, 0);
+
+        assert!(player.interacts_with(&log));
+        assert!(log.interacts_with(&player));
+        assert!(!player.interacts_with(&zone));
+        assert!(!log.interacts_with(&Collider::solid(LOG_SIZE, LAYER_OBSTACLE, 0)));
+    }
+
+    #[test]
+    fn solids_hit_and_triggers_only_report() {
+        let (mut world, system) = collision_world(0.0);
+        let player = spawn(&mut world, player_collider(), Vec2::ZERO);
+        let log = spawn(&mut world, Collider::solid(LOG_SIZE, LAYER_OBSTACLE, 0), Vec2::ZERO);
+        let coin = spawn(&mut world, Collider::trigger(LOG_SIZE, LAYER_PICKUP, LAYER_PLAYER), Vec2::ZERO);
+        // Overlaps everything, but nothing is looking for zones
+        spawn(&mut world, Collider::trigger(LOG_SIZE, LAYER_ZONE, 0), Vec2::ZERO);
+
+        let events = collisions(&mut world, system);
+        assert_eq!(events.len(), 2);
+        let kind_with = |other: Entity| events.iter().find(|event| event.other(player) == Some(other)).map(|event| event.kind);
+        assert_eq!(kind_with(log), Some(CollisionKind::Hit));
+        assert_eq!(kind_with(coin), Some(CollisionKind::Trigger));
+    }
+
+    #[test]
+    fn a_pair_collides_once_until_it_separates() {
+        let (mut world, system) = collision_world(0.0);
+        spawn(&mut world, player_collider(), Vec2::ZERO);
+        let log = spawn(&mut world, Collider::solid(LOG_SIZE, LAYER_OBSTACLE, 0), Vec2::ZERO);
+
+        assert_eq!(collisions(&mut world, system).len(), 1);
+        assert!(collisions(&mut world, system).is_empty());
+        assert!(collisions(&mut world, system).is_empty());
+
+        place(&mut world, log, Vec2::new(0.0, 100.0));
+        assert!(collisions(&mut world, system).is_empty());
+
+        place(&mut world, log, Vec2::ZERO);
+        assert_eq!(collisions(&mut world, system).len(), 1);
+    }
+
+    #[test]
+    fn finds_what_scrolled_past_into_the_cell_below() {
+        let scroll = 20.0;
+        let (mut world, system) = collision_world(scroll);
+        // The player's bottom edge is just above the boundary between cell 0 and cell -1
+        spawn(&mut world, player_collider(), Vec2::new(0.0, PLAYER_SIZE.y / 2.0 + 0.5));
+        // The log came down through the player and ended up entirely in cell -1
+        let log = spawn(&mut world, Collider::solid(LOG_SIZE, LAYER_OBSTACLE, 0), Vec2::new(0.0, -8.0));
+        world.entity_mut(log).insert(GridObject);
+
+        let events = collisions(&mut world, system);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].kind, CollisionKind::Hit);
+    }
+}
@@ -0,0 +1,126 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+use crate::cli;
+
+const DAILY_FLAG: &str = "--daily";
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+#[derive(Resource, Clone, Debug, PartialEq, Eq)]
+pub enum GameMode {
+    Normal,
+    // Everyone gets the same world for the day, `day` counts from 1970-01-01 (UTC)
+    // and `date` is the same day as YYYY-MM-DD. Only the day's first run counts,
+    // `scored` says whether this is it.
+    Daily { day: u64, date: String, scored: bool },
+}
+
+impl GameMode {
+    pub fn load() -> Self {
+        if cli::has_flag(DAILY_FLAG) {
+            let day = days_since_epoch();
+            GameMode::Daily { day, date: date_string(day), scored: false }
+        } else {
+            GameMode::Normal
+        }
+    }
+
+    // Moves a daily run on to today's course, for sessions left running past midnight
+    pub fn roll_over_to(&mut self, today: u64) {
+        if let GameMode::Daily { day, date, scored } = self {
+            if *day != today {
+                *day = today;
+                *date = date_string(today);
+                *scored = false;
+            }
+        }
+    }
+
+    pub fn daily_date(&self) -> Option<&str> {
+        match self {
+            GameMode::Daily { date, .. } => Some(date),
+            GameMode::Normal => None,
+        }
+    }
+
+    // Whether this run's score goes on a leaderboard
+    pub fn is_scored(&self) -> bool {
+        match self {
+            GameMode::Daily { scored, .. } => *scored,
+            GameMode::Normal => true,
+        }
+    }
+}
+
+// Today in UTC, so everyone switches to the next course at the same moment
+pub fn days_since_epoch() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs() / SECONDS_PER_DAY)
+        .unwrap_or(0)
+}
+
+// The world seed for a day. Spread out so neighbouring days don't get similar seeds.
+pub fn daily_seed(days: u64) -> u64 {
+    let mut seed = days.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    seed = (seed ^ (seed >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    seed = (seed ^ (seed >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    seed ^ (seed >> 31)
+}
+
+// Days since 1970-01-01 as YYYY-MM-DD, from Howard Hinnant's civil_from_days
+pub fn date_string(days: u64) -> String {
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dates_count_from_the_epoch() {
+        assert_eq!(date_string(0), "1970-01-01");
+        assert_eq!(date_string(20088), "2024-12-31");
+        assert_eq!(date_string(20089), "2025-01-01");
+    }
+
+    #[test]
+    fn leap_days_follow_the_gregorian_rules() {
+        assert_eq!(date_string(11016), "2000-02-29");
+        // 2100 isn't a leap year
+        assert_eq!(date_string(47540), "2100-02-28");
+        assert_eq!(date_string(47541), "2100-03-01");
+    }
+
+    #[test]
+    fn neighbouring_days_get_different_seeds() {
+        for day in 20000..20100 {
+            assert_ne!(daily_seed(day), daily_seed(day + 1));
+        }
+        assert_eq!(daily_seed(20000), daily_seed(20000));
+    }
+
+    #[test]
+    fn rolling_over_starts_a_new_unscored_day() {
+        let mut mode = GameMode::Daily { day: 11016, date: date_string(11016), scored: true };
+        mode.roll_over_to(11016);
+        assert!(mode.is_scored());
+
+        mode.roll_over_to(11017);
+        assert_eq!(mode, GameMode::Daily { day: 11017, date: "2000-03-01".to_string(), scored: false });
+
+        let mut normal = GameMode::Normal;
+        normal.roll_over_to(11017);
+        assert_eq!(normal, GameMode::Normal);
+    }
+}
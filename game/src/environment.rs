@@ -0,0 +1,76 @@
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+use crate::world_grid::{GridObject, ENVIRONMENT_Z, TILE_SIZE};
+use rand::Rng;
+use crate::moving_road::RoadSpan;
+use crate::world_chunks::{ChunkSpawner, CHUNK_ROWS};
+
+const POOL_KEY: &str = "scenery";
+
+#[derive(Component)]
+pub struct EnvironmentObject {
+    pub side: Side, // Venstre eller højre side af vejen
+}
+
+#[derive(Component, Clone, Copy, PartialEq)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+#[derive(Component)]
+pub struct Environment;
+
+// Scenery stage of the chunk pipeline, now and then a tree or cactus beside the road
+pub fn generate_scenery(spawner: &mut ChunkSpawner, rng: &mut impl Rng) {
+    for row in 0..CHUNK_ROWS {
+        let y = spawner.chunk.row_y(row);
+        let (road_left, road_right) = road_edges(&spawner.chunk.spans(row));
+
+        // Reduced probability for more spacing/randomness (15%)
+        if rng.gen_bool(0.15) {
+            let offset = rng.gen_range(60.0..100.0);
+            spawn_environment_object(spawner, road_left - offset, y, TILE_SIZE, Side::Left, rng);
+        }
+
+        if rng.gen_bool(0.15) {
+            let offset = rng.gen_range(60.0..100.0);
+            spawn_environment_object(spawner, road_right + offset, y, TILE_SIZE, Side::Right, rng);
+        }
+    }
+}
+
+// The middle of the leftmost and rightmost road tiles in a row, scenery goes outside them
+fn road_edges(spans: &[RoadSpan]) -> (f32, f32) {
+    let half_tile = TILE_SIZE / 2.0;
+    match (spans.first(), spans.last()) {
+        (Some(first), Some(last)) => (first.left + half_tile, last.right - half_tile),
+        _ => (0.0, 0.0),
+    }
+}
+
+fn spawn_environment_object(
+    spawner: &mut ChunkSpawner,
+    x: f32,
+    y: f32,
+    size: f32,
+    side: Side,
+    rng: &mut impl Rng,
+) {
+    let is_cactus = rng.gen_bool(0.5);
+    let texture_path = if is_cactus { "tileset/cactus.png" } else { "tileset/tree.png" };
+    let image = spawner.asset_server.load(texture_path);
+
+    spawner.spawn(POOL_KEY, (
+        Sprite {
+            image,
+            custom_size: Some(Vec2::new(size, size)),
+            anchor: Anchor::Center,
+            ..default()
+        },
+        Transform::from_xyz(x, y, ENVIRONMENT_Z),
+        EnvironmentObject { side },
+        Environment,
+        GridObject,
+    ));
+}
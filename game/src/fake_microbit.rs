@@ -0,0 +1,151 @@
+// A pretend micro:bit on a pseudo-terminal, for testing the serial path without hardware.
+// The game (or a test) opens `port_name()` as if it were the real board.
+
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use microbit_protocol::{self as protocol, Command, Message, PROTOCOL_VERSION};
+use serialport::{SerialPort, TTYPort};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ScriptStep {
+    // Message body, the checksum is added when sending
+    Send(String),
+    // Sent exactly as written, for legacy or deliberately broken lines
+    Raw(String),
+    Wait(Duration),
+}
+
+// One step per line: a message body such as `BTN A DOWN`, `wait <ms>`, `raw <text>`,
+// or `repeat <n> <body>`. Empty lines and lines starting with `#` are skipped.
+pub fn parse_script(source: &str) -> Result<Vec<ScriptStep>, String> {
+    let mut steps = Vec::new();
+
+    for (number, line) in source.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(rest) = line.strip_prefix("repeat ") {
+            let (count, body) = rest.trim().split_once(' ')
+                .and_then(|(count, body)| Some((count.parse::<usize>().ok()?, body.trim())))
+                .ok_or_else(|| format!("line {}: invalid repeat '{}'", number + 1, line))?;
+            steps.extend(std::iter::repeat_n(ScriptStep::Send(body.to_string()), count));
+            continue;
+        }
+
+        let step = if let Some(ms) = line.strip_prefix("wait ") {
+            let ms = ms.trim().parse().map_err(|_| format!("line {}: invalid wait '{}'", number + 1, line))?;
+            ScriptStep::Wait(Duration::from_millis(ms))
+        } else if let Some(text) = line.strip_prefix("raw ") {
+            ScriptStep::Raw(text.to_string())
+        } else {
+            ScriptStep::Send(line.to_string())
+        };
+        steps.push(step);
+    }
+
+    Ok(steps)
+}
+
+pub fn load_script(path: impl AsRef<Path>) -> Result<Vec<ScriptStep>, String> {
+    let source = fs::read_to_string(path.as_ref())
+        .map_err(|err| format!("{}: {}", path.as_ref().display(), err))?;
+    parse_script(&source)
+}
+
+pub struct FakeMicrobit {
+    master: TTYPort,
+    // Kept open so the pty doesn't hang up between the game's reconnects
+    _slave: TTYPort,
+    port_name: String,
+    pending: String,
+}
+
+impl FakeMicrobit {
+    pub fn open() -> serialport::Result<Self> {
+        let (master, mut slave) = TTYPort::pair()?;
+        // The game has to be able to open the same device again
+        slave.set_exclusive(false)?;
+        let port_name = slave.name().unwrap_or_default();
+
+        Ok(Self {
+            master,
+            _slave: slave,
+            port_name,
+            pending: String::new(),
+        })
+    }
+
+    pub fn port_name(&self) -> &str {
+        &self.port_name
+    }
+
+    pub fn send(&mut self, message: Message) -> io::Result<()> {
+        self.send_line(&message.to_string())
+    }
+
+    pub fn send_line(&mut self, body: &str) -> io::Result<()> {
+        let mut line = String::new();
+        // Formatting into a String cannot fail
+        protocol::write_line(&mut line, body).ok();
+        self.send_raw(&line)
+    }
+
+    pub fn send_raw(&mut self, line: &str) -> io::Result<()> {
+        self.master.write_all(line.as_bytes())?;
+        self.master.write_all(b"\n")?;
+        self.master.flush()
+    }
+
+    pub fn play(&mut self, steps: &[ScriptStep]) -> io::Result<()> {
+        for step in steps {
+            match step {
+                ScriptStep::Send(body) => self.send_line(body)?,
+                ScriptStep::Raw(line) => self.send_raw(line)?,
+                ScriptStep::Wait(duration) => thread::sleep(*duration),
+            }
+        }
+        Ok(())
+    }
+
+    // Next complete line written by the game, None if nothing arrived in time
+    pub fn read_line(&mut self, timeout: Duration) -> io::Result<Option<String>> {
+        let deadline = Instant::now() + timeout;
+        let mut buffer = [0u8; 64];
+
+        loop {
+            if let Some(end) = self.pending.find('\n') {
+                let line = self.pending[..end].trim().to_string();
+                self.pending.drain(..=end);
+                return Ok(Some(line));
+            }
+            if Instant::now() >= deadline {
+                return Ok(None);
+            }
+
+            match self.master.read(&mut buffer) {
+                Ok(count) => self.pending.push_str(&String::from_utf8_lossy(&buffer[..count])),
+                Err(err) if err.kind() == io::ErrorKind::TimedOut => {}
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    // Waits for the game to open the port and say HELLO, then answers like the firmware does
+    pub fn wait_for_game(&mut self, timeout: Duration) -> io::Result<()> {
+        let deadline = Instant::now() + timeout;
+
+        while let Some(line) = self.read_line(deadline.saturating_duration_since(Instant::now()))? {
+            if let Ok(Command::Hello { .. }) = protocol::parse_command(&line) {
+                return self.send(Message::Hello { version: PROTOCOL_VERSION });
+            }
+        }
+
+        Err(io::Error::new(io::ErrorKind::TimedOut, "the game never opened the port"))
+    }
+}
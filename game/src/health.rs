@@ -0,0 +1,265 @@
+use bevy::prelude::*;
+use crate::character::{Player, SCREEN_BOUND};
+use crate::cli;
+use crate::moving_road::MovingRoad;
+use crate::pickup::ActivePowerUps;
+
+const LIVES_FLAG: &str = "--lives";
+const DEFAULT_LIVES: u32 = 3;
+const INVINCIBILITY_SECS: f32 = 1.5;
+const BLINK_SECS: f32 = 0.1;
+// Sideways push after a hit, slowing down by this factor per second
+const KNOCKBACK_SPEED: f32 = 180.0;
+const KNOCKBACK_DECAY: f32 = 6.0;
+
+// Lives at the start of a run, set with `--lives <n>`
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct LivesConfig(pub u32);
+
+impl LivesConfig {
+    pub fn load() -> Self {
+        let Some(value) = cli::flag_value(LIVES_FLAG) else {
+            return Self(DEFAULT_LIVES);
+        };
+
+        match value.parse::<u32>() {
+            Ok(lives) if lives > 0 => Self(lives),
+            _ => {
+                warn!("Ugyldigt antal liv '{}', bruger {}", value, DEFAULT_LIVES);
+                Self(DEFAULT_LIVES)
+            }
+        }
+    }
+}
+
+impl Default for LivesConfig {
+    fn default() -> Self {
+        Self(DEFAULT_LIVES)
+    }
+}
+
+#[derive(Component, Debug)]
+pub struct Lives {
+    pub remaining: u32,
+}
+
+// Hits are ignored while this is on the player
+#[derive(Component)]
+pub struct Invincible {
+    timer: Timer,
+    blink: Timer,
+}
+
+impl Invincible {
+    pub fn new() -> Self {
+        Self {
+            timer: Timer::from_seconds(INVINCIBILITY_SECS, TimerMode::Once),
+            blink: Timer::from_seconds(BLINK_SECS, TimerMode::Repeating),
+        }
+    }
+}
+
+impl Default for Invincible {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Horizontal velocity left over from a hit
+#[derive(Component)]
+pub struct Knockback(pub f32);
+
+// The player ran into something solid
+#[derive(Event, Clone, Copy, Debug)]
+pub struct PlayerHit {
+    pub player: Entity,
+    pub by: Entity,
+}
+
+#[derive(Component)]
+pub struct LivesDisplay;
+
+pub fn setup_lives_display(mut commands: Commands, config: Res<LivesConfig>) {
+    commands.spawn((
+        Text::new(lives_text(config.0)),
+        TextFont {
+            font_size: 24.0,
+            ..default()
+        },
+        TextColor(Color::srgb(1.0, 0.4, 0.4)),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(0.0),
+            right: Val::Px(0.0),
+            ..default()
+        },
+        LivesDisplay,
+    ));
+}
+
+fn lives_text(lives: u32) -> String {
+    format!("Lives: {}", lives)
+}
+
+pub fn apply_player_hits(
+    mut commands: Commands,
+    mut hits: EventReader<PlayerHit>,
+    mut player_query: Query<(&mut Player, &mut Lives, &Transform), Without<Invincible>>,
+    transform_query: Query<&Transform>,
+    road_query: Query<(), With<MovingRoad>>,
+    mut power_ups: ResMut<ActivePowerUps>,
+) {
+    // Invincible is only added at the end of the frame, so count one hit per player ourselves
+    let mut already_hit = Vec::new();
+
+    for hit in hits.read() {
+        if already_hit.contains(&hit.player) {
+            continue;
+        }
+        let Ok((mut player, mut lives, transform)) = player_query.get_mut(hit.player) else {
+            continue;
+        };
+        already_hit.push(hit.player);
+
+        // The shield takes the hit, the blinking shows it's gone
+        if power_ups.consume_shield() {
+            commands.entity(hit.player).insert(Invincible::new());
+            continue;
+        }
+
+        lives.remaining = lives.remaining.saturating_sub(1);
+        if lives.remaining == 0 {
+            player.is_dead = true;
+            continue;
+        }
+
+        // Away from whatever was hit, or towards the middle if it was straight ahead
+        let player_x = transform.translation.x;
+        let obstacle_x = transform_query.get(hit.by).map(|t| t.translation.x).unwrap_or(player_x);
+        let mut direction = if player_x != obstacle_x {
+            (player_x - obstacle_x).signum()
+        } else if player_x > 0.0 {
+            -1.0
+        } else {
+            1.0
+        };
+        // Running off the road is reported as hitting the road, that one pushes back onto it
+        if road_query.contains(hit.by) {
+            direction = -direction;
+        }
+
+        commands.entity(hit.player).insert((Invincible::new(), Knockback(direction * KNOCKBACK_SPEED)));
+    }
+}
+
+pub fn update_invincibility(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Invincible, &mut Visibility)>,
+) {
+    for (entity, mut invincible, mut visibility) in query.iter_mut() {
+        invincible.timer.tick(time.delta());
+        invincible.blink.tick(time.delta());
+
+        if invincible.timer.finished() {
+            *visibility = Visibility::Inherited;
+            commands.entity(entity).remove::<Invincible>();
+            continue;
+        }
+
+        if invincible.blink.just_finished() {
+            *visibility = match *visibility {
+                Visibility::Hidden => Visibility::Inherited,
+                _ => Visibility::Hidden,
+            };
+        }
+    }
+}
+
+pub fn apply_knockback(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Knockback, &mut Transform)>,
+) {
+    for (entity, mut knockback, mut transform) in query.iter_mut() {
+        transform.translation.x = (transform.translation.x + knockback.0 * time.delta_secs())
+            .clamp(-SCREEN_BOUND, SCREEN_BOUND);
+        knockback.0 *= (-KNOCKBACK_DECAY * time.delta_secs()).exp();
+
+        if knockback.0.abs() < 1.0 {
+            commands.entity(entity).remove::<Knockback>();
+        }
+    }
+}
+
+pub fn update_lives_display(
+    lives_query: Query<&Lives, Changed<Lives>>,
+    mut display_query: Query<&mut Text, With<LivesDisplay>>,
+) {
+    for lives in lives_query.iter() {
+        for mut text in display_query.iter_mut() {
+            text.0 = lives_text(lives.remaining);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+
+    fn world_with_player(lives: u32) -> (World, Entity, Entity) {
+        let mut world = World::new();
+        world.init_resource::<Events<PlayerHit>>();
+        world.init_resource::<ActivePowerUps>();
+        let player = world
+            .spawn((Player::default(), Lives { remaining: lives }, Transform::default()))
+            .id();
+        let obstacle = world.spawn(Transform::from_xyz(10.0, 0.0, 0.0)).id();
+        (world, player, obstacle)
+    }
+
+    fn hit(world: &mut World, player: Entity, by: Entity) {
+        world.send_event(PlayerHit { player, by });
+        world.run_system_once(apply_player_hits).unwrap();
+    }
+
+    #[test]
+    fn a_hit_takes_a_life_and_makes_the_player_invincible() {
+        let (mut world, player, obstacle) = world_with_player(3);
+        hit(&mut world, player, obstacle);
+
+        assert_eq!(world.get::<Lives>(player).unwrap().remaining, 2);
+        assert!(world.get::<Invincible>(player).is_some());
+        assert!(world.get::<Knockback>(player).unwrap().0 < 0.0);
+    }
+
+    #[test]
+    fn a_hit_while_invincible_does_not_take_a_life() {
+        let (mut world, player, obstacle) = world_with_player(3);
+        world.entity_mut(player).insert(Invincible::new());
+        hit(&mut world, player, obstacle);
+
+        assert_eq!(world.get::<Lives>(player).unwrap().remaining, 3);
+    }
+
+    #[test]
+    fn the_shield_takes_the_hit_instead_of_a_life() {
+        let (mut world, player, obstacle) = world_with_player(3);
+        world.resource_mut::<ActivePowerUps>().shield = Some(Timer::from_seconds(5.0, TimerMode::Once));
+        hit(&mut world, player, obstacle);
+
+        assert_eq!(world.get::<Lives>(player).unwrap().remaining, 3);
+        assert!(world.resource::<ActivePowerUps>().shield.is_none());
+        assert!(world.get::<Invincible>(player).is_some());
+    }
+
+    #[test]
+    fn several_hits_in_one_frame_take_one_life() {
+        let (mut world, player, obstacle) = world_with_player(3);
+        world.send_event(PlayerHit { player, by: obstacle });
+        hit(&mut world, player, obstacle);
+
+        assert_eq!(world.get::<Lives>(player).unwrap().remaining, 2);
+    }
+}
@@ -0,0 +1,238 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+
+use bevy::input::gamepad::{GamepadConnection, GamepadConnectionEvent};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::microbit::ControllerEvent;
+
+const CONTROLS_FILE: &str = "controls.json";
+// How far the stick has to be pushed before the runner moves
+const DEFAULT_STICK_DEAD_ZONE: f32 = 0.2;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Action {
+    Jump,
+    Duck,
+    MoveLeft,
+    MoveRight,
+    Confirm,
+    Restart,
+    Pause,
+}
+
+impl Action {
+    pub const ALL: [Action; 7] = [
+        Action::Jump,
+        Action::Duck,
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::Confirm,
+        Action::Restart,
+        Action::Pause,
+    ];
+}
+
+// The micro:bit events that can be bound, tilt is analog and handled by TiltSteering
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SerialButton {
+    Jump,
+    Left,
+    Right,
+    Pause,
+    Shake,
+}
+
+impl SerialButton {
+    fn from_event(event: &ControllerEvent) -> Option<Self> {
+        match event {
+            ControllerEvent::Jump => Some(SerialButton::Jump),
+            ControllerEvent::Left => Some(SerialButton::Left),
+            ControllerEvent::Right => Some(SerialButton::Right),
+            ControllerEvent::Pause => Some(SerialButton::Pause),
+            ControllerEvent::Shake => Some(SerialButton::Shake),
+            ControllerEvent::Tilt(_) => None,
+        }
+    }
+}
+
+#[derive(Resource, Clone, Debug)]
+pub struct ActionMap {
+    pub keyboard: HashMap<Action, Vec<KeyCode>>,
+    pub gamepad: HashMap<Action, Vec<GamepadButton>>,
+    pub serial: HashMap<Action, Vec<SerialButton>>,
+    pub stick_dead_zone: f32,
+}
+
+// Only the actions listed in the file replace the defaults
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct ControlsFile {
+    keyboard: HashMap<Action, Vec<KeyCode>>,
+    gamepad: HashMap<Action, Vec<GamepadButton>>,
+    serial: HashMap<Action, Vec<SerialButton>>,
+    stick_dead_zone: Option<f32>,
+}
+
+impl Default for ActionMap {
+    fn default() -> Self {
+        let keyboard = HashMap::from([
+            (Action::Jump, vec![KeyCode::Space]),
+            (Action::Duck, vec![KeyCode::KeyS, KeyCode::ArrowDown]),
+            (Action::MoveLeft, vec![KeyCode::KeyA, KeyCode::ArrowLeft]),
+            (Action::MoveRight, vec![KeyCode::KeyD, KeyCode::ArrowRight]),
+            (Action::Confirm, vec![KeyCode::Enter]),
+            (Action::Restart, vec![KeyCode::KeyR]),
+            (Action::Pause, vec![KeyCode::Escape, KeyCode::KeyP]),
+        ]);
+        let gamepad = HashMap::from([
+            (Action::Jump, vec![GamepadButton::South]),
+            (Action::Duck, vec![GamepadButton::DPadDown]),
+            (Action::MoveLeft, vec![GamepadButton::DPadLeft]),
+            (Action::MoveRight, vec![GamepadButton::DPadRight]),
+            (Action::Confirm, vec![GamepadButton::South]),
+            (Action::Restart, vec![GamepadButton::Start]),
+            (Action::Pause, vec![GamepadButton::Start]),
+        ]);
+        let serial = HashMap::from([
+            (Action::Jump, vec![SerialButton::Jump, SerialButton::Shake]),
+            (Action::MoveLeft, vec![SerialButton::Left]),
+            (Action::MoveRight, vec![SerialButton::Right]),
+            (Action::Confirm, vec![SerialButton::Jump]),
+            (Action::Restart, vec![SerialButton::Pause]),
+            (Action::Pause, vec![SerialButton::Pause]),
+        ]);
+
+        Self {
+            keyboard,
+            gamepad,
+            serial,
+            stick_dead_zone: DEFAULT_STICK_DEAD_ZONE,
+        }
+    }
+}
+
+impl ActionMap {
+    pub fn load() -> Self {
+        let mut map = Self::default();
+
+        let Ok(data) = fs::read_to_string(CONTROLS_FILE) else {
+            return map;
+        };
+        match serde_json::from_str::<ControlsFile>(&data) {
+            Ok(file) => {
+                map.keyboard.extend(file.keyboard);
+                map.gamepad.extend(file.gamepad);
+                map.serial.extend(file.serial);
+                if let Some(dead_zone) = file.stick_dead_zone {
+                    map.stick_dead_zone = dead_zone.clamp(0.0, 0.95);
+                }
+            }
+            Err(err) => warn!("Kunne ikke læse {}: {}", CONTROLS_FILE, err),
+        }
+        map
+    }
+}
+
+// What the player is doing this frame, whatever device it came from
+#[derive(Resource, Default)]
+pub struct ActionState {
+    pressed: HashSet<Action>,
+    just_pressed: HashSet<Action>,
+    // Fired by one-shot sources like the micro:bit, which have no "held" state
+    pulsed: HashSet<Action>,
+    // Left stick, -1 (left) to 1 (right) with the dead zone taken out
+    move_axis: f32,
+}
+
+impl ActionState {
+    pub fn pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.just_pressed.contains(&action)
+    }
+
+    pub fn pulsed(&self, action: Action) -> bool {
+        self.pulsed.contains(&action)
+    }
+
+    pub fn move_axis(&self) -> f32 {
+        self.move_axis
+    }
+}
+
+// Pads that are plugged in right now, by entity. Bevy removes the Gamepad
+// component on disconnect, so a pad that goes away stops counting as held.
+#[derive(Resource, Default)]
+pub struct ConnectedGamepads(pub HashMap<Entity, String>);
+
+pub fn track_gamepads(
+    mut connection_events: EventReader<GamepadConnectionEvent>,
+    mut connected: ResMut<ConnectedGamepads>,
+) {
+    for event in connection_events.read() {
+        match &event.connection {
+            GamepadConnection::Connected { name, .. } => {
+                info!("Gamepad tilsluttet: {}", name);
+                connected.0.insert(event.gamepad, name.clone());
+            }
+            GamepadConnection::Disconnected => {
+                let name = connected.0.remove(&event.gamepad).unwrap_or_default();
+                info!("Gamepad frakoblet: {}", name);
+            }
+        }
+    }
+}
+
+// Rescales so movement starts from zero just outside the dead zone
+fn apply_dead_zone(value: f32, dead_zone: f32) -> f32 {
+    if value.abs() <= dead_zone {
+        return 0.0;
+    }
+    (value.signum() * (value.abs() - dead_zone) / (1.0 - dead_zone)).clamp(-1.0, 1.0)
+}
+
+pub fn update_action_state(
+    map: Res<ActionMap>,
+    keys: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    mut controller_events: EventReader<ControllerEvent>,
+    mut state: ResMut<ActionState>,
+) {
+    let serial: HashSet<SerialButton> = controller_events
+        .read()
+        .filter_map(SerialButton::from_event)
+        .collect();
+
+    // The pad pushed the furthest wins
+    state.move_axis = gamepads
+        .iter()
+        .map(|gamepad| apply_dead_zone(gamepad.left_stick().x, map.stick_dead_zone))
+        .fold(0.0, |axis: f32, value| if value.abs() > axis.abs() { value } else { axis });
+
+    let previous = std::mem::take(&mut state.pressed);
+    state.just_pressed.clear();
+    state.pulsed.clear();
+
+    for action in Action::ALL {
+        let bound_keys = map.keyboard.get(&action).map(Vec::as_slice).unwrap_or_default();
+        let bound_buttons = map.gamepad.get(&action).map(Vec::as_slice).unwrap_or_default();
+        let bound_serial = map.serial.get(&action).map(Vec::as_slice).unwrap_or_default();
+
+        let held = keys.any_pressed(bound_keys.iter().copied())
+            || gamepads.iter().any(|gamepad| gamepad.any_pressed(bound_buttons.iter().copied()));
+        let pulsed = bound_serial.iter().any(|button| serial.contains(button));
+
+        if held || pulsed {
+            state.pressed.insert(action);
+            if !previous.contains(&action) || pulsed {
+                state.just_pressed.insert(action);
+            }
+        }
+        if pulsed {
+            state.pulsed.insert(action);
+        }
+    }
+}
@@ -0,0 +1,21 @@
+pub mod character;
+pub mod background;
+pub mod moving_road;
+pub mod collision;
+pub mod health;
+pub mod obstacle;
+pub mod obstacle_chunks;
+pub mod pickup;
+pub mod pool;
+pub mod spatial_grid;
+pub mod environment;
+pub mod world_grid;
+pub mod world_chunks;
+pub mod microbit;
+pub mod scoreboard;
+pub mod cli;
+pub mod daily;
+pub mod input;
+pub mod world_seed;
+#[cfg(unix)]
+pub mod fake_microbit;
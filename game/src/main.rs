@@ -0,0 +1,184 @@
+use bevy::prelude::*;
+use jump_and_run_platform::background::{self, *};
+use jump_and_run_platform::character::*;
+use jump_and_run_platform::moving_road::*;
+use jump_and_run_platform::collision::*;
+use jump_and_run_platform::environment::*;
+use jump_and_run_platform::world_grid::*;
+use jump_and_run_platform::obstacle::ObstacleKind;
+use jump_and_run_platform::obstacle_chunks::setup_obstacle_generator;
+use jump_and_run_platform::spatial_grid::SpatialGrid;
+use jump_and_run_platform::pool::{recycle_grid_objects, setup_entity_pool};
+use jump_and_run_platform::world_chunks::{retire_chunks, setup_world_chunks, spawn_chunks};
+use jump_and_run_platform::daily::GameMode;
+use jump_and_run_platform::world_seed::{setup_world_rng, WorldRng, WorldSeed};
+use jump_and_run_platform::pickup::{
+    attract_coins, collect_pickups, setup_pickups, update_power_up_display, update_power_ups,
+    ActivePowerUps, Pickup, PowerUpDisplay
+};
+use jump_and_run_platform::health::{
+    apply_knockback, apply_player_hits, setup_lives_display, update_invincibility,
+    update_lives_display, LivesConfig, LivesDisplay, PlayerHit
+};
+use bevy::input::InputSystem;
+use jump_and_run_platform::input::{
+    track_gamepads, update_action_state, Action, ActionMap, ActionState, ConnectedGamepads
+};
+use jump_and_run_platform::microbit::{
+    drain_controller_events, setup_serial_display, setup_serial_listener, sync_serial_state,
+    update_serial_display, update_tilt_steering, ControllerChannel, ControllerEvent,
+    MalformedLineCount, SerialConnectionState, SerialOutbox, SerialSettings, SharedSerialState,
+    TiltSteering
+};
+use jump_and_run_platform::scoreboard::{
+    announce_score_milestones, increase_score_system, scoreboard_system, setup_scoreboard,
+    ScoreText, ScoreTimer,
+    update_death_scoreboard_ui, handle_input_system, update_name_input_display,
+    DeathScoreDisplay, GameState
+};
+
+fn main() {
+    let serial_settings = SerialSettings::load();
+    let game_mode = GameMode::load();
+    let world_seed = WorldSeed::load(&game_mode);
+
+    App::new()
+        .add_plugins(DefaultPlugins.set(ImagePlugin::default_nearest())) // prevents blurry sprites
+        .init_state::<GameState>()
+        .add_event::<ControllerEvent>()
+        .add_event::<CollisionEvent>()
+        .add_event::<PlayerHit>()
+        .insert_resource(LivesConfig::load())
+        .insert_resource(WorldRng::new(world_seed.seed))
+        .insert_resource(world_seed)
+        .insert_resource(game_mode)
+        .init_resource::<ControllerChannel>()
+        .init_resource::<SerialOutbox>()
+        .insert_resource(TiltSteering::new(serial_settings.tilt))
+        .insert_resource(serial_settings)
+        .init_resource::<SharedSerialState>()
+        .init_resource::<SerialConnectionState>()
+        .init_resource::<MalformedLineCount>()
+        .insert_resource(ActionMap::load())
+        .init_resource::<ActionState>()
+        .init_resource::<ConnectedGamepads>()
+        .init_resource::<SpatialGrid>()
+        .insert_resource(<ScoreText>::default())
+        .insert_resource(ScoreTimer(Timer::from_seconds(1.0 / 3.0, TimerMode::Repeating)))
+        .insert_resource(ScrollSpeedTimer(Timer::from_seconds(10., TimerMode::Repeating)))
+        .init_resource::<ActivePowerUps>()
+        
+        // Controller input has to be in place before any gameplay system reads it
+        .add_systems(PreUpdate, (
+            track_gamepads,
+            (drain_controller_events, update_action_state).chain(),
+        ).after(InputSystem))
+
+        // One-time setup
+        .add_systems(Startup, (
+            (setup_serial_listener, setup_serial_display).chain(),
+            setup_pixel_grid,
+        ))
+
+        // Gameplay setup (runs on restart)
+        .add_systems(OnEnter(GameState::Playing), (
+            setup_world_rng,
+            setup_entity_pool,
+            setup_scoreboard,
+            setup_world_grid,
+            setup_road,
+            setup_character,
+            setup_lives_display,
+            setup_pickups,
+            setup_obstacle_generator,
+            setup_world_chunks,
+        ).chain())
+
+        // Cleanup when game over ends (restarting)
+        .add_systems(OnExit(GameState::GameOver), cleanup_game_over)
+        
+        .add_systems(Update, (
+            execute_animations,
+            handle_player_death,
+            increase_score_system,
+            announce_score_milestones,
+            scoreboard_system,
+            // The world is generated a chunk at a time ahead of the screen, at the speed
+            // it had before a death stops it
+            (spawn_chunks, retire_chunks, update_road)
+                .chain()
+                .after(move_grid_objects)
+                .before(handle_player_death),
+            increase_scroll_speed,
+            move_character_horizontal,
+            animate_sprite,
+            (jump, update_player_shadow).chain(),
+            move_grid_objects,
+            recycle_grid_objects.after(move_grid_objects),
+            (sync_spatial_grid, check_collision, apply_collision_hits, check_off_road, apply_player_hits)
+                .chain()
+                .after(move_grid_objects),
+            toggle_pause,
+        ).run_if(in_state(GameState::Playing)))
+
+        .add_systems(Update, (
+            update_invincibility,
+            apply_knockback.after(move_character_horizontal),
+            update_lives_display,
+            track_player_lane.after(move_grid_objects),
+        ).run_if(in_state(GameState::Playing)))
+
+        .add_systems(Update, (
+            attract_coins.after(move_grid_objects).before(check_collision),
+            collect_pickups.after(check_collision),
+            update_power_ups,
+            update_power_up_display.after(update_power_ups),
+        ).run_if(in_state(GameState::Playing)))
+
+        .add_systems(Update, (
+            update_death_scoreboard_ui,
+            handle_input_system,
+            update_name_input_display,
+        ).run_if(in_state(GameState::GameOver)))
+
+        // Always run
+        .add_systems(Update, (
+            background::fit_canvas,
+            (sync_serial_state, update_tilt_steering, update_serial_display).chain(),
+        ))
+        
+        .run();
+}
+
+// Pausing stops virtual time, which freezes every system that reads Res<Time>
+fn toggle_pause(
+    actions: Res<ActionState>,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    if actions.just_pressed(Action::Pause) {
+        if time.is_paused() {
+            time.unpause();
+        } else {
+            time.pause();
+        }
+    }
+}
+
+fn cleanup_game_over(
+    mut commands: Commands,
+    query: Query<Entity, Or<(
+        With<Player>, 
+        With<MovingRoad>, 
+        With<EnvironmentObject>, 
+        With<ObstacleKind>,
+        With<TileGrid>,
+        With<DeathScoreDisplay>,
+        With<LivesDisplay>,
+        With<Pickup>,
+        With<PowerUpDisplay>
+    )>>,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
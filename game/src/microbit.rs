@@ -0,0 +1,583 @@
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use crossbeam_channel::{Receiver, Sender};
+use serde::Deserialize;
+use serialport::{SerialPort, SerialPortType};
+use std::time::Duration;
+
+use bevy::prelude::*;
+use crate::cli;
+use microbit_protocol::{self as protocol, Button, Command, Gesture, Icon, Message, PROTOCOL_VERSION};
+
+const SERIAL_CONFIG_FILE: &str = "microbit.json";
+const SERIAL_PORT_ENV: &str = "MICROBIT_PORT";
+const SERIAL_PORT_FLAG: &str = "--serial-port";
+const DEFAULT_BAUD_RATE: u32 = 115200;
+const READ_TIMEOUT: Duration = Duration::from_millis(100);
+const MIN_RECONNECT_DELAY: Duration = Duration::from_millis(250);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(5);
+const TILT_CALIBRATION_SAMPLES: u32 = 20;
+// Messages for the board beyond this are dropped rather than piling up
+const OUTBOX_CAPACITY: usize = 32;
+
+// USB ids of the DAPLink interface chip on the micro:bit
+const MICROBIT_VID: u16 = 0x0D28;
+const MICROBIT_PID: u16 = 0x0204;
+
+#[derive(Event, Clone, Copy, Debug, PartialEq)]
+pub enum ControllerEvent {
+    Jump,
+    Left,
+    Right,
+    Tilt(f32),
+    Pause,
+    Shake,
+}
+
+// Every line from the board becomes one message, so presses between frames are not merged
+#[derive(Resource)]
+pub struct ControllerChannel {
+    pub sender: Sender<ControllerEvent>,
+    pub receiver: Receiver<ControllerEvent>,
+}
+
+impl Default for ControllerChannel {
+    fn default() -> Self {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        Self { sender, receiver }
+    }
+}
+
+// Things the game shows on the board's LED matrix
+#[derive(Clone, Debug, PartialEq)]
+pub enum BoardMessage {
+    Icon(Icon),
+    Score(u32),
+    Scroll(String),
+    Clear,
+}
+
+impl BoardMessage {
+    fn as_command(&self) -> Command<'_> {
+        match self {
+            BoardMessage::Icon(icon) => Command::Icon(*icon),
+            BoardMessage::Score(score) => Command::Score(*score),
+            BoardMessage::Scroll(text) => Command::Scroll(text),
+            BoardMessage::Clear => Command::Clear,
+        }
+    }
+}
+
+// Outbound queue, flushed by the reader thread while a board is connected
+#[derive(Resource)]
+pub struct SerialOutbox {
+    sender: Sender<BoardMessage>,
+    receiver: Receiver<BoardMessage>,
+}
+
+impl Default for SerialOutbox {
+    fn default() -> Self {
+        let (sender, receiver) = crossbeam_channel::bounded(OUTBOX_CAPACITY);
+        Self { sender, receiver }
+    }
+}
+
+impl SerialOutbox {
+    pub fn send(&self, message: BoardMessage) {
+        // A full queue means no board is reading, so dropping is fine
+        let _ = self.sender.try_send(message);
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PortSource {
+    CommandLine,
+    Environment,
+    ConfigFile,
+    AutoDetected,
+}
+
+impl PortSource {
+    fn label(&self) -> &'static str {
+        match self {
+            PortSource::CommandLine => "cli",
+            PortSource::Environment => "env",
+            PortSource::ConfigFile => "config",
+            PortSource::AutoDetected => "auto",
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+struct SerialConfigFile {
+    port: Option<String>,
+    baud_rate: Option<u32>,
+    #[serde(default)]
+    tilt: TiltConfig,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+#[serde(default)]
+pub struct TiltConfig {
+    // Tilt (in g, after calibration) that is ignored around the rest position
+    pub dead_zone: f32,
+    // How much tilt gives full speed: 2.0 means half a g
+    pub sensitivity: f32,
+}
+
+impl Default for TiltConfig {
+    fn default() -> Self {
+        Self {
+            dead_zone: 0.1,
+            sensitivity: 2.0,
+        }
+    }
+}
+
+// Serial settings, resolved in order: CLI flag, env var, config file, auto-detect
+#[derive(Resource, Clone, Debug)]
+pub struct SerialSettings {
+    pub port_override: Option<(String, PortSource)>,
+    pub baud_rate: u32,
+    pub tilt: TiltConfig,
+}
+
+impl SerialSettings {
+    pub fn load() -> Self {
+        let file = load_serial_config().unwrap_or_default();
+
+        let port_override = cli::flag_value(SERIAL_PORT_FLAG)
+            .map(|port| (port, PortSource::CommandLine))
+            .or_else(|| std::env::var(SERIAL_PORT_ENV).ok().map(|port| (port, PortSource::Environment)))
+            .or_else(|| file.port.map(|port| (port, PortSource::ConfigFile)))
+            .filter(|(port, _)| !port.trim().is_empty());
+
+        Self {
+            port_override,
+            baud_rate: file.baud_rate.unwrap_or(DEFAULT_BAUD_RATE),
+            tilt: file.tilt,
+        }
+    }
+
+    // Skips the CLI/env/config lookup, for tools and tests that already know the port
+    pub fn with_port(port: impl Into<String>) -> Self {
+        Self {
+            port_override: Some((port.into(), PortSource::CommandLine)),
+            baud_rate: DEFAULT_BAUD_RATE,
+            tilt: TiltConfig::default(),
+        }
+    }
+
+    pub fn resolve_port(&self) -> Option<(String, PortSource)> {
+        self.port_override
+            .clone()
+            .or_else(|| find_microbit_port().map(|port| (port, PortSource::AutoDetected)))
+    }
+}
+
+#[derive(Resource, Clone, Debug, Default, PartialEq, Eq)]
+pub enum SerialConnectionState {
+    #[default]
+    Disconnected,
+    Connecting,
+    Connected { port: String },
+}
+
+// Written by the reader thread, mirrored into SerialConnectionState every frame
+#[derive(Resource, Default)]
+pub struct SharedSerialState(pub Arc<Mutex<SerialConnectionState>>);
+
+// Number of lines from the board that could not be parsed
+#[derive(Resource, Default)]
+pub struct MalformedLineCount(pub Arc<AtomicU32>);
+
+// Analog steering from the accelerometer's x axis
+#[derive(Resource)]
+pub struct TiltSteering {
+    pub config: TiltConfig,
+    // Latest reading in g, None while no board is sending
+    pub reading: Option<f32>,
+    // Reading when the board is held level
+    pub neutral: f32,
+    calibration: Option<(u32, f32)>,
+}
+
+impl TiltSteering {
+    pub fn new(config: TiltConfig) -> Self {
+        Self {
+            config,
+            reading: None,
+            neutral: 0.0,
+            calibration: None,
+        }
+    }
+
+    // Averages the next readings into the new rest position
+    pub fn start_calibration(&mut self) {
+        self.calibration = Some((0, 0.0));
+    }
+
+    pub fn is_calibrating(&self) -> bool {
+        self.calibration.is_some()
+    }
+
+    pub fn add_reading(&mut self, x: f32) {
+        self.reading = Some(x);
+
+        if let Some((count, sum)) = self.calibration.as_mut() {
+            *count += 1;
+            *sum += x;
+
+            if *count >= TILT_CALIBRATION_SAMPLES {
+                self.neutral = *sum / *count as f32;
+                self.calibration = None;
+            }
+        }
+    }
+
+    // Steering between -1 and 1
+    pub fn axis(&self) -> f32 {
+        let Some(reading) = self.reading else {
+            return 0.0;
+        };
+        if self.is_calibrating() {
+            return 0.0;
+        }
+
+        let tilt = reading - self.neutral;
+        let dead_zone = self.config.dead_zone.clamp(0.0, 0.99);
+        if tilt.abs() <= dead_zone {
+            return 0.0;
+        }
+
+        // Start from zero at the edge of the dead zone instead of jumping
+        let tilt = tilt.signum() * (tilt.abs() - dead_zone) / (1.0 - dead_zone);
+        (tilt * self.config.sensitivity).clamp(-1.0, 1.0)
+    }
+}
+
+#[derive(Component)]
+pub struct SerialPortDisplay;
+
+pub fn find_microbit_port() -> Option<String> {
+    let ports = serialport::available_ports().ok()?;
+
+    ports.into_iter()
+        .find(|port| matches!(
+            &port.port_type,
+            SerialPortType::UsbPort(info) if info.vid == MICROBIT_VID && info.pid == MICROBIT_PID
+        ))
+        .map(|port| port.port_name)
+}
+
+fn load_serial_config() -> Option<SerialConfigFile> {
+    let data = fs::read_to_string(SERIAL_CONFIG_FILE).ok()?;
+
+    match serde_json::from_str(&data) {
+        Ok(config) => Some(config),
+        Err(err) => {
+            warn!("Kunne ikke læse {}: {}", SERIAL_CONFIG_FILE, err);
+            None
+        }
+    }
+}
+
+pub fn setup_serial_listener(
+    settings: Res<SerialSettings>,
+    channel: Res<ControllerChannel>,
+    outbox: Res<SerialOutbox>,
+    shared_state: Res<SharedSerialState>,
+    malformed: Res<MalformedLineCount>,
+) {
+    let settings = settings.clone();
+    let link = SerialLink {
+        events: channel.sender.clone(),
+        outbox: outbox.receiver.clone(),
+        state: shared_state.0.clone(),
+        malformed: malformed.0.clone(),
+    };
+
+    thread::spawn(move || run_serial_supervisor(settings, link));
+}
+
+// Everything the reader thread shares with the game
+struct SerialLink {
+    events: Sender<ControllerEvent>,
+    outbox: Receiver<BoardMessage>,
+    state: Arc<Mutex<SerialConnectionState>>,
+    malformed: Arc<AtomicU32>,
+}
+
+// Keeps (re)connecting to the micro:bit for as long as the game runs,
+// so the board can be plugged in or swapped mid-game
+fn run_serial_supervisor(settings: SerialSettings, link: SerialLink) {
+    let mut backoff = MIN_RECONNECT_DELAY;
+    let mut last_error = String::new();
+
+    loop {
+        set_connection_state(&link.state, SerialConnectionState::Connecting);
+
+        let error = match settings.resolve_port() {
+            Some((port_name, source)) => {
+                match open_port(&port_name, settings.baud_rate) {
+                    Ok(port) => {
+                        info!("micro:bit forbundet på {} ({})", port_name, source.label());
+                        set_connection_state(&link.state, SerialConnectionState::Connected { port: port_name.clone() });
+                        backoff = MIN_RECONNECT_DELAY;
+                        last_error.clear();
+
+                        let err = run_connection(port, &link);
+                        format!("Forbindelsen til {} blev afbrudt: {}", port_name, err)
+                    }
+                    Err(err) => format!("Kunne ikke åbne {}: {}", port_name, err),
+                }
+            }
+            None => format!("Ingen micro:bit fundet - brug {} eller {}", SERIAL_PORT_FLAG, SERIAL_PORT_ENV),
+        };
+
+        // Only report a failure once, not on every retry
+        if error != last_error {
+            warn!("{}", error);
+            last_error = error;
+        }
+
+        set_connection_state(&link.state, SerialConnectionState::Disconnected);
+        thread::sleep(backoff);
+        backoff = (backoff * 2).min(MAX_RECONNECT_DELAY);
+    }
+}
+
+fn open_port(port_name: &str, baud_rate: u32) -> serialport::Result<Box<dyn SerialPort>> {
+    let builder = serialport::new(port_name, baud_rate).timeout(READ_TIMEOUT);
+    // Unix raises DTR on open by itself, and setting it again fails on
+    // pseudo-terminals such as the fake micro:bit's
+    #[cfg(unix)]
+    let builder = builder.preserve_dtr_on_open();
+    builder.open()
+}
+
+// Reads lines and writes queued commands until the port fails; the returned error says why
+fn run_connection(port: Box<dyn SerialPort>, link: &SerialLink) -> io::Error {
+    let mut reader = BufReader::new(port);
+    let mut line = String::new();
+    // Boards that announce a version we don't speak are ignored until they say HELLO again
+    let mut compatible = true;
+
+    // Whatever was queued while nothing was connected is stale by now
+    link.outbox.try_iter().for_each(drop);
+
+    if let Err(err) = write_command(reader.get_mut(), Command::Hello { version: PROTOCOL_VERSION }) {
+        return err;
+    }
+
+    loop {
+        // The read timeout doubles as the interval for flushing the outbox
+        for message in link.outbox.try_iter() {
+            if let Err(err) = write_command(reader.get_mut(), message.as_command()) {
+                return err;
+            }
+        }
+
+        match reader.read_line(&mut line) {
+            Ok(0) => return io::Error::new(io::ErrorKind::UnexpectedEof, "porten blev lukket"),
+            Ok(_) => {
+                match protocol::parse_line(&line) {
+                    Ok(Message::Hello { version }) => {
+                        compatible = version == PROTOCOL_VERSION;
+                        if compatible {
+                            info!("micro:bit taler protokol v{}", version);
+                        } else {
+                            warn!("micro:bit taler protokol v{}, spillet forventer v{}", version, PROTOCOL_VERSION);
+                        }
+                    }
+                    Ok(message) if compatible => {
+                        if let Some(event) = controller_event(message) {
+                            if link.events.send(event).is_err() {
+                                return io::Error::new(io::ErrorKind::BrokenPipe, "spillet lukkede kanalen");
+                            }
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(err) => {
+                        let count = link.malformed.fetch_add(1, Ordering::Relaxed) + 1;
+                        warn!("Ugyldig linje fra micro:bit ({}, {} i alt): {:?}", err, count, line.trim());
+                    }
+                }
+                line.clear();
+            }
+            // No data within the timeout, keep whatever partial line we have
+            Err(err) if err.kind() == io::ErrorKind::TimedOut => continue,
+            Err(err) if err.kind() == io::ErrorKind::InvalidData => {
+                link.malformed.fetch_add(1, Ordering::Relaxed);
+                line.clear();
+            }
+            Err(err) => return err,
+        }
+    }
+}
+
+fn write_command(port: &mut Box<dyn SerialPort>, command: Command) -> io::Result<()> {
+    let mut line = String::new();
+    // Formatting into a String cannot fail
+    protocol::write_line(&mut line, command).ok();
+    line.push('\n');
+    port.write_all(line.as_bytes())
+}
+
+fn controller_event(message: Message) -> Option<ControllerEvent> {
+    match message {
+        Message::Button { button: Button::A, pressed: true } => Some(ControllerEvent::Jump),
+        Message::Button { button: Button::B, pressed: true } => Some(ControllerEvent::Pause),
+        Message::Accel { x, .. } => Some(ControllerEvent::Tilt(x as f32 / 1024.0)),
+        Message::Gesture(Gesture::Shake) => Some(ControllerEvent::Shake),
+        Message::Gesture(Gesture::Left) => Some(ControllerEvent::Left),
+        Message::Gesture(Gesture::Right) => Some(ControllerEvent::Right),
+        Message::LegacyJump => Some(ControllerEvent::Jump),
+        _ => None,
+    }
+}
+
+fn set_connection_state(state: &Arc<Mutex<SerialConnectionState>>, new_state: SerialConnectionState) {
+    if let Ok(mut lock) = state.lock() {
+        *lock = new_state;
+    }
+}
+
+// Forwards everything the reader thread received since last frame as Bevy events
+pub fn drain_controller_events(
+    channel: Res<ControllerChannel>,
+    mut events: EventWriter<ControllerEvent>,
+) {
+    for event in channel.receiver.try_iter() {
+        events.send(event);
+    }
+}
+
+pub fn sync_serial_state(
+    shared_state: Res<SharedSerialState>,
+    mut state: ResMut<SerialConnectionState>,
+) {
+    if let Ok(lock) = shared_state.0.lock() {
+        if *state != *lock {
+            *state = lock.clone();
+        }
+    }
+}
+
+// Feeds accelerometer readings into the steering and recalibrates on connect or C
+pub fn update_tilt_steering(
+    mut controller_events: EventReader<ControllerEvent>,
+    mut tilt: ResMut<TiltSteering>,
+    state: Res<SerialConnectionState>,
+    kb_input: Res<ButtonInput<KeyCode>>,
+) {
+    if state.is_changed() {
+        if matches!(*state, SerialConnectionState::Connected { .. }) {
+            tilt.start_calibration();
+        } else {
+            tilt.reading = None;
+        }
+    }
+
+    if kb_input.just_pressed(KeyCode::KeyC) {
+        tilt.start_calibration();
+    }
+
+    for event in controller_events.read() {
+        if let ControllerEvent::Tilt(x) = event {
+            tilt.add_reading(*x);
+        }
+    }
+}
+
+fn serial_status_text(state: &SerialConnectionState, tilt: &TiltSteering) -> String {
+    match state {
+        SerialConnectionState::Disconnected => "micro:bit: disconnected".to_string(),
+        SerialConnectionState::Connecting => "micro:bit: connecting...".to_string(),
+        SerialConnectionState::Connected { port } if tilt.is_calibrating() => {
+            format!("micro:bit: {} - hold it level to calibrate", port)
+        }
+        SerialConnectionState::Connected { port } => format!("micro:bit: {}", port),
+    }
+}
+
+pub fn setup_serial_display(
+    mut commands: Commands,
+    state: Res<SerialConnectionState>,
+    tilt: Res<TiltSteering>,
+) {
+    commands.spawn((
+        Text::new(serial_status_text(&state, &tilt)),
+        TextFont {
+            font_size: 16.0,
+            ..default()
+        },
+        TextColor(Color::srgb(0.7, 0.7, 0.7)),
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(0.0),
+            left: Val::Px(0.0),
+            ..default()
+        },
+        SerialPortDisplay,
+    ));
+}
+
+pub fn update_serial_display(
+    state: Res<SerialConnectionState>,
+    tilt: Res<TiltSteering>,
+    mut query: Query<&mut Text, With<SerialPortDisplay>>,
+) {
+    if !state.is_changed() && !tilt.is_changed() {
+        return;
+    }
+
+    for mut text in query.iter_mut() {
+        let status = serial_status_text(&state, &tilt);
+        if text.0 != status {
+            text.0 = status;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn steering_with(reading: f32) -> TiltSteering {
+        let mut tilt = TiltSteering::new(TiltConfig::default());
+        tilt.add_reading(reading);
+        tilt
+    }
+
+    #[test]
+    fn tilt_inside_the_dead_zone_does_not_steer() {
+        let dead_zone = TiltConfig::default().dead_zone;
+        for reading in [0.0, dead_zone / 2.0, -dead_zone / 2.0, dead_zone, -dead_zone] {
+            assert_eq!(steering_with(reading).axis(), 0.0, "steered at {} g", reading);
+        }
+        assert!(steering_with(dead_zone + 0.05).axis() > 0.0);
+        assert!(steering_with(-dead_zone - 0.05).axis() < 0.0);
+    }
+
+    #[test]
+    fn calibration_moves_the_rest_position() {
+        let mut tilt = TiltSteering::new(TiltConfig::default());
+        tilt.start_calibration();
+        for _ in 0..TILT_CALIBRATION_SAMPLES {
+            // No steering while the board is being held still
+            assert_eq!(tilt.axis(), 0.0);
+            tilt.add_reading(0.4);
+        }
+
+        assert!(!tilt.is_calibrating());
+        tilt.add_reading(0.45);
+        assert_eq!(tilt.axis(), 0.0);
+        tilt.add_reading(0.0);
+        assert!(tilt.axis() < 0.0);
+    }
+}
@@ -0,0 +1,748 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+use rand::Rng;
+use crate::character::Player;
+use crate::health::{Invincible, PlayerHit};
+use crate::world_chunks::{ChunkContext, CHUNK_ROWS};
+use crate::world_grid::{GridConfig, GRID_SIZE_X, GRID_SIZE_Y, ROAD_Z, TILE_SIZE};
+
+// The sides of a tile the road leaves through
+pub const ROAD_UP: u8 = 1 << 0;
+pub const ROAD_DOWN: u8 = 1 << 1;
+pub const ROAD_LEFT: u8 = 1 << 2;
+pub const ROAD_RIGHT: u8 = 1 << 3;
+
+// The player may run this far beside the road before it counts as leaving it
+pub const ROAD_SHOULDER: f32 = TILE_SIZE;
+// From the middle of a straight road to the outer edge of its shoulder
+pub const ROAD_HALF_WIDTH: f32 = TILE_SIZE / 2.0 + ROAD_SHOULDER;
+
+// The road is a fixed ring of rows holding the rows of every live world chunk.
+// At most four chunks are live at once, while a new one is spawned before the
+// oldest is retired.
+pub const ROAD_RING_ROWS: usize = 4 * CHUNK_ROWS as usize;
+// Centre of row 0, the bottom row of the screen when the run starts
+pub const ROAD_START_Y: f32 = -(GRID_SIZE_Y as f32) * TILE_SIZE / 2.0 + TILE_SIZE / 2.0;
+
+// The road stays this far from the sides, leaving room for scenery
+const MIN_COLUMN: u32 = 3;
+const MAX_COLUMN: u32 = GRID_SIZE_X - 1 - MIN_COLUMN;
+// Straight rows between turns, enough for the player to catch up with the road at top speed
+const MIN_STRAIGHT: u32 = 6;
+const MAX_STRAIGHT: u32 = 14;
+// Columns between the middle of a fork and each branch
+const FORK_SPREAD: u32 = 2;
+const FORK_LENGTH: u32 = 12;
+const FORK_CHANCE: f64 = 0.25;
+// Rows are generated this far past the chunk being built, so obstacles can be fitted to where the road goes
+const ROAD_LOOKAHEAD_ROWS: usize = 2 * CHUNK_ROWS as usize;
+// Sprites in each row of the ring, enough for the junction at a fork
+const TILES_PER_ROW: usize = 2 * FORK_SPREAD as usize + 1;
+
+// One of the road sprites in the ring, showing tile `index` of the row in `slot`
+#[derive(Component)]
+pub struct MovingRoad {
+    pub slot: usize,
+    pub index: usize,
+}
+
+pub type SegmentId = u32;
+
+// Which way the player went at the last fork
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Lane {
+    #[default]
+    Main,
+    Left,
+    Right,
+}
+
+// What a stretch of road has on it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BranchProfile {
+    pub name: &'static str,
+    // Chance of each obstacle in a chunk being kept
+    pub obstacle_chance: f64,
+    pub coins_per_row: u32,
+}
+
+pub const MAIN_ROAD: BranchProfile = BranchProfile { name: "main", obstacle_chance: 1.0, coins_per_row: 3 };
+// One side of every fork is quiet, the other pays for its obstacles with coins
+pub const SAFE_BRANCH: BranchProfile = BranchProfile { name: "safe", obstacle_chance: 0.35, coins_per_row: 1 };
+pub const RISKY_BRANCH: BranchProfile = BranchProfile { name: "risky", obstacle_chance: 1.0, coins_per_row: 6 };
+
+// A stretch of road from one junction to the next
+#[derive(Debug)]
+pub struct RoadSegment {
+    pub lane: Lane,
+    pub profile: BranchProfile,
+    // Where the road goes on from here, both branches when it forks
+    pub next: Vec<SegmentId>,
+}
+
+// The segments of road from the bottom of the screen up to the last generated row, linked
+// the way the road runs. The tiles of a row name their segment. Ids only grow along the
+// road, so everything below the screen has the smallest ones.
+#[derive(Resource, Default)]
+pub struct RoadGraph {
+    segments: BTreeMap<SegmentId, RoadSegment>,
+    next_id: SegmentId,
+}
+
+impl RoadGraph {
+    // Adds a segment the road continues into from each of `from`
+    pub fn add(&mut self, lane: Lane, profile: BranchProfile, from: &[SegmentId]) -> SegmentId {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        for previous in from {
+            if let Some(segment) = self.segments.get_mut(previous) {
+                segment.next.push(id);
+            }
+        }
+        self.segments.insert(id, RoadSegment { lane, profile, next: Vec::new() });
+        id
+    }
+
+    pub fn get(&self, id: SegmentId) -> Option<&RoadSegment> {
+        self.segments.get(&id)
+    }
+
+    pub fn lane(&self, id: SegmentId) -> Lane {
+        self.get(id).map_or(Lane::Main, |segment| segment.lane)
+    }
+
+    pub fn profile(&self, id: SegmentId) -> BranchProfile {
+        self.get(id).map_or(MAIN_ROAD, |segment| segment.profile)
+    }
+
+    // Whether the road runs straight on from `from` into `to`
+    pub fn leads_to(&self, from: SegmentId, to: SegmentId) -> bool {
+        self.get(from).is_some_and(|segment| segment.next.contains(&to))
+    }
+
+    // Drops the segments that have scrolled away
+    pub fn forget_before(&mut self, id: SegmentId) {
+        self.segments = self.segments.split_off(&id);
+    }
+}
+
+// One tile of a road row
+#[derive(Clone, Copy, Debug)]
+pub struct RoadRowTile {
+    pub column: u32,
+    // The sides the road leaves the tile through
+    pub exits: u8,
+    pub segment: SegmentId,
+}
+
+const fn tile(column: u32, exits: u8, segment: SegmentId) -> RoadRowTile {
+    RoadRowTile { column, exits, segment }
+}
+
+struct Fork {
+    left: SegmentId,
+    right: SegmentId,
+    // Rows left before the two branches join up again
+    rows_left: u32,
+}
+
+#[derive(Resource)]
+pub struct RoadGenerator {
+    // The column the road leaves the last row through, the middle of the fork while it's split
+    column: u32,
+    straight_left: u32,
+    segment: SegmentId,
+    fork: Option<Fork>,
+    // Rows generated ahead of the chunks, oldest first
+    ahead: VecDeque<Vec<RoadRowTile>>,
+}
+
+impl RoadGenerator {
+    pub fn new(graph: &mut RoadGraph) -> Self {
+        // The first screen is straight, so the run starts calmly
+        Self {
+            column: GRID_SIZE_X / 2,
+            straight_left: GRID_SIZE_Y + MIN_STRAIGHT,
+            segment: graph.add(Lane::Main, MAIN_ROAD, &[]),
+            fork: None,
+            ahead: VecDeque::new(),
+        }
+    }
+
+    pub fn next_row(&mut self, graph: &mut RoadGraph, rng: &mut impl Rng) -> Vec<RoadRowTile> {
+        if let Some(fork) = &mut self.fork {
+            if fork.rows_left > 0 {
+                fork.rows_left -= 1;
+                return vec![
+                    tile(self.column - FORK_SPREAD, ROAD_UP | ROAD_DOWN, fork.left),
+                    tile(self.column + FORK_SPREAD, ROAD_UP | ROAD_DOWN, fork.right),
+                ];
+            }
+
+            self.segment = graph.add(Lane::Main, MAIN_ROAD, &[fork.left, fork.right]);
+            self.fork = None;
+            self.straight_left = rng.gen_range(MIN_STRAIGHT..=MAX_STRAIGHT);
+            return junction_row(self.column, ROAD_DOWN, self.segment);
+        }
+
+        if self.straight_left > 0 {
+            self.straight_left -= 1;
+            return vec![tile(self.column, ROAD_UP | ROAD_DOWN, self.segment)];
+        }
+        self.straight_left = rng.gen_range(MIN_STRAIGHT..=MAX_STRAIGHT);
+
+        let can_fork = self.column >= MIN_COLUMN + FORK_SPREAD && self.column + FORK_SPREAD <= MAX_COLUMN;
+        if can_fork && rng.gen_bool(FORK_CHANCE) {
+            let (left_profile, right_profile) = if rng.gen_bool(0.5) {
+                (RISKY_BRANCH, SAFE_BRANCH)
+            } else {
+                (SAFE_BRANCH, RISKY_BRANCH)
+            };
+            self.fork = Some(Fork {
+                left: graph.add(Lane::Left, left_profile, &[self.segment]),
+                right: graph.add(Lane::Right, right_profile, &[self.segment]),
+                rows_left: FORK_LENGTH,
+            });
+            return junction_row(self.column, ROAD_UP, self.segment);
+        }
+
+        let go_left = match (self.column > MIN_COLUMN, self.column < MAX_COLUMN) {
+            (true, true) => rng.gen_bool(0.5),
+            (can_go_left, _) => can_go_left,
+        };
+        let (from, to, out, back) = if go_left {
+            (self.column, self.column - 1, ROAD_LEFT, ROAD_RIGHT)
+        } else {
+            (self.column, self.column + 1, ROAD_RIGHT, ROAD_LEFT)
+        };
+        self.column = to;
+
+        vec![tile(from, ROAD_DOWN | out, self.segment), tile(to, back | ROAD_UP, self.segment)]
+    }
+}
+
+// The row where the road splits in two (`ends` is ROAD_UP), or where the
+// two branches join up again (`ends` is ROAD_DOWN)
+fn junction_row(middle: u32, ends: u8, segment: SegmentId) -> Vec<RoadRowTile> {
+    let through = if ends == ROAD_UP { ROAD_DOWN } else { ROAD_UP };
+
+    let mut row = vec![tile(middle, through | ROAD_LEFT | ROAD_RIGHT, segment)];
+    for offset in 1..FORK_SPREAD {
+        row.push(tile(middle - offset, ROAD_LEFT | ROAD_RIGHT, segment));
+        row.push(tile(middle + offset, ROAD_LEFT | ROAD_RIGHT, segment));
+    }
+    row.push(tile(middle - FORK_SPREAD, ends | ROAD_RIGHT, segment));
+    row.push(tile(middle + FORK_SPREAD, ends | ROAD_LEFT, segment));
+    row
+}
+
+pub fn column_x(column: u32) -> f32 {
+    (column as f32 - (GRID_SIZE_X / 2) as f32) * TILE_SIZE
+}
+
+// The image for a tile with these exits, whether to flip it upside down and how far to turn it.
+// The curves in the tileset come up from the bottom, the T-junctions run from bottom to top.
+fn tile_image(exits: u8) -> (&'static str, bool, f32) {
+    const ALONG: u8 = ROAD_UP | ROAD_DOWN;
+    const ACROSS: u8 = ROAD_LEFT | ROAD_RIGHT;
+    const DOWN_LEFT: u8 = ROAD_DOWN | ROAD_LEFT;
+    const DOWN_RIGHT: u8 = ROAD_DOWN | ROAD_RIGHT;
+    const UP_LEFT: u8 = ROAD_UP | ROAD_LEFT;
+    const UP_RIGHT: u8 = ROAD_UP | ROAD_RIGHT;
+    const ALONG_LEFT: u8 = ALONG | ROAD_LEFT;
+    const ALONG_RIGHT: u8 = ALONG | ROAD_RIGHT;
+    const ACROSS_DOWN: u8 = ACROSS | ROAD_DOWN;
+    const ACROSS_UP: u8 = ACROSS | ROAD_UP;
+
+    match exits {
+        ACROSS => ("tileset/stright_road.png", false, FRAC_PI_2),
+        DOWN_LEFT => ("tileset/left_road.png", false, 0.0),
+        DOWN_RIGHT => ("tileset/right_road.png", false, 0.0),
+        UP_LEFT => ("tileset/left_road.png", true, 0.0),
+        UP_RIGHT => ("tileset/right_road.png", true, 0.0),
+        ALONG_LEFT => ("tileset/left_t_turn.png", false, 0.0),
+        ALONG_RIGHT => ("tileset/right_t_turn.png", false, 0.0),
+        // Turning the left T a quarter either way puts its stem at the bottom or top
+        ACROSS_DOWN => ("tileset/left_t_turn.png", false, FRAC_PI_2),
+        ACROSS_UP => ("tileset/left_t_turn.png", false, -FRAC_PI_2),
+        _ => ("tileset/stright_road.png", false, 0.0),
+    }
+}
+
+// Shows `tile` on a ring sprite, or hides the sprite when its row has fewer tiles
+fn show_road_tile(
+    tile: Option<&RoadRowTile>,
+    asset_server: &AssetServer,
+    transform: &mut Transform,
+    sprite: &mut Sprite,
+    visibility: &mut Visibility,
+) {
+    let Some(tile) = tile else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+
+    let (image, flip_y, rotation) = tile_image(tile.exits);
+    sprite.image = asset_server.load(image);
+    sprite.flip_y = flip_y;
+    transform.translation.x = column_x(tile.column);
+    transform.rotation = Quat::from_rotation_z(rotation);
+    *visibility = Visibility::Inherited;
+}
+
+// A stretch of road across one row, one per branch
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RoadSpan {
+    pub left: f32,
+    pub right: f32,
+    pub segment: SegmentId,
+}
+
+impl RoadSpan {
+    pub fn middle(&self) -> f32 {
+        (self.left + self.right) / 2.0
+    }
+
+    // Whether a player at `x` is on this bit of road or its shoulder
+    pub fn reaches(&self, x: f32) -> bool {
+        x >= self.left - ROAD_SHOULDER && x <= self.right + ROAD_SHOULDER
+    }
+}
+
+// Where row `row` is once the world has scrolled `scroll_px`
+pub fn row_y(row: u64, scroll_px: f32) -> f32 {
+    ROAD_START_Y + row as f32 * TILE_SIZE - scroll_px
+}
+
+// The stretches of road in a row, from left to right.
+// Tiles next to each other belong to the same stretch.
+pub fn row_spans(row: &[RoadRowTile]) -> Vec<RoadSpan> {
+    let mut tiles = row.to_vec();
+    tiles.sort_by_key(|tile| tile.column);
+
+    let mut spans: Vec<RoadSpan> = Vec::new();
+    for tile in tiles {
+        let x = column_x(tile.column);
+        let (left, right) = (x - TILE_SIZE / 2.0, x + TILE_SIZE / 2.0);
+        match spans.last_mut() {
+            Some(last) if left <= last.right => last.right = right,
+            _ => spans.push(RoadSpan { left, right, segment: tile.segment }),
+        }
+    }
+    spans
+}
+
+// The generated road rows, kept in a ring of ROAD_RING_ROWS slots. Every row
+// is placed from the scroll alone, at a whole number of tiles from row 0,
+// so rows can't drift apart or leave seams. Rows come and go with their world
+// chunk, a slot stays empty until its row has been generated.
+#[derive(Resource)]
+pub struct RoadRing {
+    // The lowest row in the ring, row `n` is kept in slot `n % ROAD_RING_ROWS`
+    first_row: u64,
+    rows: Vec<Vec<RoadRowTile>>,
+    // Rows generated so far, the ones that don't fit in the ring yet wait in `upcoming`
+    generated: u64,
+    upcoming: VecDeque<Vec<RoadRowTile>>,
+    // Slots whose row changed since the sprites were last updated
+    refilled: Vec<usize>,
+    scroll_px: f32,
+}
+
+impl Default for RoadRing {
+    fn default() -> Self {
+        Self {
+            first_row: 0,
+            rows: vec![Vec::new(); ROAD_RING_ROWS],
+            generated: 0,
+            upcoming: VecDeque::new(),
+            refilled: Vec::new(),
+            scroll_px: 0.0,
+        }
+    }
+}
+
+impl RoadRing {
+
+    fn slot(row: u64) -> usize {
+        (row % ROAD_RING_ROWS as u64) as usize
+    }
+
+    // The row kept in `slot` right now
+    fn row_in_slot(&self, slot: usize) -> u64 {
+        let first_slot = Self::slot(self.first_row);
+        self.first_row + ((slot + ROAD_RING_ROWS - first_slot) % ROAD_RING_ROWS) as u64
+    }
+
+    pub fn row_y(&self, row: u64) -> f32 {
+        row_y(row, self.scroll_px)
+    }
+
+    pub fn slot_y(&self, slot: usize) -> f32 {
+        self.row_y(self.row_in_slot(slot))
+    }
+
+    pub fn tile(&self, slot: usize, index: usize) -> Option<&RoadRowTile> {
+        self.rows[slot].get(index)
+    }
+
+    // Every row in the ring and its y, from the bottom up
+    pub fn row_positions(&self) -> impl Iterator<Item = (u64, f32)> + '_ {
+        (self.first_row..self.first_row + ROAD_RING_ROWS as u64).map(|row| (row, self.row_y(row)))
+    }
+
+    // Adds the next generated row on top of the road
+    pub fn push_row(&mut self, row: Vec<RoadRowTile>) {
+        if self.generated < self.first_row + ROAD_RING_ROWS as u64 {
+            let slot = Self::slot(self.generated);
+            self.rows[slot] = row;
+            self.refilled.push(slot);
+        } else {
+            self.upcoming.push_back(row);
+        }
+        self.generated += 1;
+    }
+
+    pub fn advance(&mut self, scroll_px: f32) {
+        self.scroll_px = scroll_px;
+    }
+
+    // Retires the rows below `row` along with their world chunk and hands their
+    // slots to the rows waiting at the top
+    pub fn retire_before(&mut self, row: u64) {
+        while self.first_row < row {
+            let slot = Self::slot(self.first_row);
+            self.rows[slot] = self.upcoming.pop_front().unwrap_or_default();
+            self.first_row += 1;
+            self.refilled.push(slot);
+        }
+    }
+
+    // The slots that got a new row since the last call
+    pub fn take_refilled(&mut self) -> Vec<usize> {
+        std::mem::take(&mut self.refilled)
+    }
+
+    // The oldest segment still in the ring
+    pub fn oldest_segment(&self) -> Option<SegmentId> {
+        self.rows.iter().flatten().map(|tile| tile.segment).min()
+    }
+
+    // The stretches of road in the row closest to `y`
+    pub fn spans_at(&self, y: f32) -> Vec<RoadSpan> {
+        let row = ((y - ROAD_START_Y + self.scroll_px) / TILE_SIZE).round();
+        if row < self.first_row as f32 || row >= (self.first_row + ROAD_RING_ROWS as u64) as f32 {
+            return Vec::new();
+        }
+
+        row_spans(&self.rows[Self::slot(row as u64)])
+    }
+}
+
+pub fn setup_road(mut commands: Commands) {
+    let mut graph = RoadGraph::default();
+    let generator = RoadGenerator::new(&mut graph);
+    let ring = RoadRing::default();
+
+    // The sprites are spawned once and reused for every row that passes through their slot.
+    // They stay hidden until the first chunks have generated their rows.
+    for slot in 0..ROAD_RING_ROWS {
+        for index in 0..TILES_PER_ROW {
+            commands.spawn((
+                Sprite {
+                    custom_size: Some(Vec2::new(TILE_SIZE, TILE_SIZE)),
+                    anchor: Anchor::Center,
+                    ..default()
+                },
+                Transform::from_xyz(0.0, ring.slot_y(slot), ROAD_Z),
+                Visibility::Hidden,
+                MovingRoad { slot, index },
+            ));
+        }
+    }
+
+    commands.insert_resource(ring);
+    commands.insert_resource(graph);
+    commands.insert_resource(generator);
+}
+
+// Road stage of the chunk pipeline, adds the chunk's rows to the ring and
+// shows the later stages the rows generated past it
+pub fn generate_road(
+    chunk: &mut ChunkContext,
+    generator: &mut RoadGenerator,
+    graph: &mut RoadGraph,
+    ring: &mut RoadRing,
+    rng: &mut impl Rng,
+) {
+    while generator.ahead.len() < CHUNK_ROWS as usize + ROAD_LOOKAHEAD_ROWS {
+        let row = generator.next_row(graph, rng);
+        generator.ahead.push_back(row);
+    }
+
+    for row in generator.ahead.drain(..CHUNK_ROWS as usize) {
+        ring.push_row(row.clone());
+        chunk.road.push(row);
+    }
+    chunk.road_ahead = generator.ahead.iter().cloned().collect();
+}
+
+// Places the road from this frame's scroll and shows the rows that took over a slot
+pub fn update_road(
+    grid_config: Res<GridConfig>,
+    asset_server: Res<AssetServer>,
+    mut ring: ResMut<RoadRing>,
+    mut graph: ResMut<RoadGraph>,
+    mut road_query: Query<(&MovingRoad, &mut Transform, &mut Sprite, &mut Visibility)>,
+) {
+    ring.advance(grid_config.scroll_px());
+    let refilled = ring.take_refilled();
+
+    for (road, mut transform, mut sprite, mut visibility) in road_query.iter_mut() {
+        if refilled.contains(&road.slot) {
+            let tile = ring.tile(road.slot, road.index);
+            show_road_tile(tile, &asset_server, &mut transform, &mut sprite, &mut visibility);
+        }
+        transform.translation.y = ring.slot_y(road.slot);
+    }
+
+    if !refilled.is_empty() {
+        if let Some(oldest) = ring.oldest_segment() {
+            graph.forget_before(oldest);
+        }
+    }
+}
+
+// Running past the shoulder counts as a hit, like running into an obstacle
+pub fn check_off_road(
+    player_query: Query<(Entity, &Player, &Transform), Without<Invincible>>,
+    road_query: Query<(Entity, &Transform, &Visibility), With<MovingRoad>>,
+    ring: Res<RoadRing>,
+    mut hits: EventWriter<PlayerHit>,
+) {
+    for (entity, player, transform) in player_query.iter() {
+        // Jumping over the verge is fine, as long as the player lands on the road
+        if player.is_dead || player.height > 0.0 {
+            continue;
+        }
+
+        let position = transform.translation.truncate();
+        let spans = ring.spans_at(position.y);
+        if spans.is_empty() || spans.iter().any(|span| span.reaches(position.x)) {
+            continue;
+        }
+
+        // The knockback pushes the player back towards the closest bit of road
+        let nearest = road_query
+            .iter()
+            .filter(|(_, _, visibility)| **visibility != Visibility::Hidden)
+            .min_by(|(_, a, _), (_, b, _)| {
+                let a = a.translation.truncate().distance_squared(position);
+                let b = b.translation.truncate().distance_squared(position);
+                a.total_cmp(&b)
+            })
+            .map(|(road, _, _)| road);
+
+        if let Some(road) = nearest {
+            hits.send(PlayerHit { player: entity, by: road });
+        }
+    }
+}
+
+// Keeps track of which branch of a fork the player took, following the road graph from
+// the segment the player was on. Jumping across to the other branch isn't following it.
+pub fn track_player_lane(
+    mut player_query: Query<(&mut Player, &Transform)>,
+    ring: Res<RoadRing>,
+    graph: Res<RoadGraph>,
+) {
+    for (mut player, transform) in player_query.iter_mut() {
+        let position = transform.translation.truncate();
+        let Some(span) = ring.spans_at(position.y)
+            .into_iter()
+            .find(|span| span.reaches(position.x))
+        else {
+            continue;
+        };
+
+        if player.segment == Some(span.segment) {
+            continue;
+        }
+        // Without a segment to come from (a new run), the player just starts where they are
+        let followed = player.segment.is_none_or(|from| graph.leads_to(from, span.segment));
+        player.segment = Some(span.segment);
+
+        let lane = graph.lane(span.segment);
+        if lane != player.lane {
+            match (lane, followed) {
+                (Lane::Main, _) => info!("Tilbage på hovedvejen fra {:?}", player.lane),
+                (_, true) => info!("Vejvalg: {:?} ({})", lane, graph.profile(span.segment).name),
+                (_, false) => info!("Sprang over til {:?} ({})", lane, graph.profile(span.segment).name),
+            }
+            player.lane = lane;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+    use crate::pool::RECYCLE_Y;
+    use crate::world_grid::MAX_SCROLL_SPEED;
+
+    const SCREEN_HALF_HEIGHT: f32 = GRID_SIZE_Y as f32 * TILE_SIZE / 2.0;
+
+    #[test]
+    fn road_rows_never_leave_gaps_or_overlap() {
+        let mut rng = ChaCha8Rng::seed_from_u64(23);
+        let mut graph = RoadGraph::default();
+        let mut generator = RoadGenerator::new(&mut graph);
+        let mut ring = RoadRing::default();
+        // Rows come a chunk at a time, generated just before they scroll onto the screen
+        let mut generate = |ring: &mut RoadRing, scroll_px: f32| {
+            while row_y(ring.generated, scroll_px) < SCREEN_HALF_HEIGHT + TILE_SIZE {
+                for _ in 0..CHUNK_ROWS {
+                    ring.push_row(generator.next_row(&mut graph, &mut rng));
+                }
+            }
+        };
+        generate(&mut ring, 0.0);
+
+        // Scrolls the way move_grid_objects does: whole pixels out of a fractional distance
+        let mut distance_moved = 0.0_f32;
+        let mut scroll_speed = 35.0;
+        // Something scrolling with the grid, starting on row 10
+        let mut object_y = ring.row_y(10);
+
+        for frame in 0..20_000 {
+            let delta = if frame % 3 == 0 { 1.0 / 144.0 } else { 1.0 / 60.0 };
+            let previous_px = distance_moved.floor();
+            distance_moved += scroll_speed * delta;
+            object_y -= distance_moved.floor() - previous_px;
+            scroll_speed = (scroll_speed + 0.02).min(MAX_SCROLL_SPEED);
+
+            let scroll_px = distance_moved.floor();
+            generate(&mut ring, scroll_px);
+            // Rows leave with their chunk, once its top row is below the screen
+            while row_y(ring.first_row + CHUNK_ROWS as u64 - 1, scroll_px) < RECYCLE_Y {
+                ring.retire_before(ring.first_row + CHUNK_ROWS as u64);
+            }
+            ring.advance(scroll_px);
+
+            let positions: Vec<(u64, f32)> = ring.row_positions().collect();
+            for pair in positions.windows(2) {
+                assert_eq!(pair[1].0, pair[0].0 + 1);
+                assert_eq!(pair[1].1 - pair[0].1, TILE_SIZE, "gap or overlap at frame {}", frame);
+            }
+            for &(row, y) in &positions {
+                assert_eq!((y + ring.scroll_px - ROAD_START_Y) / TILE_SIZE, row as f32);
+                assert_eq!(ring.slot_y(RoadRing::slot(row)), y);
+            }
+
+            // The ring can only show the rows that have been generated
+            let top_row = ring.generated.min(ring.first_row + ROAD_RING_ROWS as u64) - 1;
+            let (bottom, top) = (positions[0].1, ring.row_y(top_row));
+            assert!(bottom - TILE_SIZE / 2.0 <= -SCREEN_HALF_HEIGHT, "bottom of the screen uncovered at frame {}", frame);
+            assert!(top + TILE_SIZE / 2.0 >= SCREEN_HALF_HEIGHT, "top of the screen uncovered at frame {}", frame);
+
+            if ring.first_row <= 10 {
+                assert_eq!(object_y, ring.row_y(10), "grid objects drifted off the road at frame {}", frame);
+            }
+        }
+
+        // Far enough that every slot has been recycled many times
+        assert!(ring.first_row > 10 * ROAD_RING_ROWS as u64);
+    }
+
+    #[test]
+    fn forks_split_and_rejoin_through_a_junction_row() {
+        let mut rng = ChaCha8Rng::seed_from_u64(22);
+        let mut graph = RoadGraph::default();
+        let mut generator = RoadGenerator::new(&mut graph);
+        let rows: Vec<Vec<RoadSpan>> = (0..2_000)
+            .map(|_| row_spans(&generator.next_row(&mut graph, &mut rng)))
+            .collect();
+        let junction_width = (2 * FORK_SPREAD + 1) as f32 * TILE_SIZE;
+
+        let mut forks = 0;
+        let mut row = 1;
+        while row < rows.len() {
+            if rows[row].len() < 2 || rows[row - 1].len() == 2 {
+                row += 1;
+                continue;
+            }
+            let split = &rows[row - 1];
+            assert_eq!(split.len(), 1);
+            assert_eq!(split[0].right - split[0].left, junction_width, "split at row {}", row - 1);
+
+            let (left, right) = (rows[row][0].segment, rows[row][1].segment);
+            assert_eq!(graph.lane(left), Lane::Left);
+            assert_eq!(graph.lane(right), Lane::Right);
+            assert_ne!(graph.profile(left), graph.profile(right));
+            assert_eq!(graph.get(split[0].segment).unwrap().next, vec![left, right]);
+
+            let end = row + FORK_LENGTH as usize;
+            if end >= rows.len() {
+                break;
+            }
+            assert!(rows[row..end].iter().all(|branches| branches.len() == 2));
+            let rejoin = &rows[end];
+            assert_eq!(rejoin.len(), 1);
+            assert_eq!(rejoin[0].right - rejoin[0].left, junction_width, "rejoin at row {}", end);
+            assert_eq!(rejoin[0].middle(), split[0].middle());
+            assert!(graph.leads_to(left, rejoin[0].segment) && graph.leads_to(right, rejoin[0].segment));
+
+            forks += 1;
+            row = end;
+        }
+        assert!(forks > 0);
+    }
+
+    #[test]
+    fn the_player_follows_their_branch_into_the_merge() {
+        let middle = GRID_SIZE_X / 2;
+        let mut graph = RoadGraph::default();
+        let main = graph.add(Lane::Main, MAIN_ROAD, &[]);
+        let left = graph.add(Lane::Left, RISKY_BRANCH, &[main]);
+        let right = graph.add(Lane::Right, SAFE_BRANCH, &[main]);
+        let merge = graph.add(Lane::Main, MAIN_ROAD, &[left, right]);
+
+        let mut ring = RoadRing::default();
+        ring.push_row(vec![tile(middle, ROAD_UP | ROAD_DOWN, main)]);
+        ring.push_row(junction_row(middle, ROAD_UP, main));
+        for _ in 0..3 {
+            ring.push_row(vec![
+                tile(middle - FORK_SPREAD, ROAD_UP | ROAD_DOWN, left),
+                tile(middle + FORK_SPREAD, ROAD_UP | ROAD_DOWN, right),
+            ]);
+        }
+        ring.push_row(junction_row(middle, ROAD_DOWN, merge));
+
+        let mut world = World::new();
+        world.insert_resource(ring);
+        world.insert_resource(graph);
+        let track = world.register_system(track_player_lane);
+        let player = world.spawn((Player::default(), Transform::default())).id();
+        let walk_to = |world: &mut World, column: u32, row: u64| {
+            let y = world.resource::<RoadRing>().row_y(row);
+            world.get_mut::<Transform>(player).unwrap().translation = Vec3::new(column_x(column), y, 0.0);
+            world.run_system(track).unwrap();
+            let player = world.get::<Player>(player).unwrap();
+            (player.lane, player.segment)
+        };
+
+        assert_eq!(walk_to(&mut world, middle, 0), (Lane::Main, Some(main)));
+        assert_eq!(walk_to(&mut world, middle - FORK_SPREAD, 1), (Lane::Main, Some(main)));
+        assert_eq!(walk_to(&mut world, middle - FORK_SPREAD, 2), (Lane::Left, Some(left)));
+        // Across the gap onto the other branch
+        assert_eq!(walk_to(&mut world, middle + FORK_SPREAD, 3), (Lane::Right, Some(right)));
+        assert_eq!(walk_to(&mut world, middle + FORK_SPREAD, 4), (Lane::Right, Some(right)));
+        assert_eq!(walk_to(&mut world, middle + FORK_SPREAD, 5), (Lane::Main, Some(merge)));
+    }
+}
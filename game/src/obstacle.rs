@@ -0,0 +1,147 @@
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+use crate::background::GAME_LAYERS;
+use crate::character::Player;
+use crate::collision::{Collider, LAYER_OBSTACLE, LAYER_PLAYER};
+use crate::world_chunks::ChunkSpawner;
+use crate::world_grid::{GridObject, LOG_Z, TILE_SIZE};
+
+// How tall the player is standing and ducking, measured up from `Player::height`
+pub const PLAYER_STANDING_HEIGHT: f32 = 24.0;
+pub const PLAYER_DUCKING_HEIGHT: f32 = 12.0;
+
+// A vertical range above the road, in pixels
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HeightSpan {
+    pub bottom: f32,
+    pub top: f32,
+}
+
+impl HeightSpan {
+    pub const fn new(bottom: f32, top: f32) -> Self {
+        Self { bottom, top }
+    }
+
+    pub fn overlaps(&self, other: &HeightSpan) -> bool {
+        self.bottom < other.top && other.bottom < self.top
+    }
+
+    pub fn of_player(player: &Player) -> Self {
+        let size = if player.is_ducking { PLAYER_DUCKING_HEIGHT } else { PLAYER_STANDING_HEIGHT };
+        Self::new(player.height, player.height + size)
+    }
+}
+
+// What the player has to do to get past an obstacle
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Avoidance {
+    Jump,
+    Dodge,
+    Duck,
+}
+
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ObstacleKind {
+    // Low, jump over it
+    Log,
+    // Too tall to jump, go around it
+    Cactus,
+    // Hangs above the road, duck under it
+    Branch,
+}
+
+impl ObstacleKind {
+    pub const ALL: [ObstacleKind; 3] = [ObstacleKind::Log, ObstacleKind::Cactus, ObstacleKind::Branch];
+
+    pub fn height_span(self) -> HeightSpan {
+        match self {
+            ObstacleKind::Log => HeightSpan::new(0.0, 10.0),
+            ObstacleKind::Cactus => HeightSpan::new(0.0, 64.0),
+            ObstacleKind::Branch => HeightSpan::new(16.0, 40.0),
+        }
+    }
+
+    pub fn avoidance(self) -> Avoidance {
+        match self {
+            ObstacleKind::Log => Avoidance::Jump,
+            ObstacleKind::Cactus => Avoidance::Dodge,
+            ObstacleKind::Branch => Avoidance::Duck,
+        }
+    }
+
+    pub fn hitbox(self) -> Vec2 {
+        match self {
+            ObstacleKind::Log => Vec2::new(16.0, 5.0),
+            ObstacleKind::Cactus => Vec2::new(12.0, 6.0),
+            ObstacleKind::Branch => Vec2::new(24.0, 4.0),
+        }
+    }
+
+    fn texture(self) -> &'static str {
+        match self {
+            ObstacleKind::Log | ObstacleKind::Branch => "tileset/log.png",
+            ObstacleKind::Cactus => "tileset/cactus.png",
+        }
+    }
+}
+
+const POOL_KEY: &str = "obstacle";
+
+pub fn spawn_obstacle(spawner: &mut ChunkSpawner, kind: ObstacleKind, position: Vec2) -> Entity {
+    let span = kind.height_span();
+
+    // Like the player, the transform is on the road and the sprite is drawn at its height
+    let (color, anchor) = match kind {
+        ObstacleKind::Branch => (
+            Color::srgb(0.6, 0.5, 0.4),
+            Anchor::Custom(Vec2::new(0., -span.bottom / TILE_SIZE)),
+        ),
+        _ => (Color::WHITE, Anchor::Center),
+    };
+
+    let image = spawner.asset_server.load(kind.texture());
+    spawner.spawn(POOL_KEY, (
+        Sprite {
+            image,
+            custom_size: Some(Vec2::new(TILE_SIZE, TILE_SIZE)),
+            color,
+            anchor,
+            ..default()
+        },
+        Transform::from_xyz(position.x, position.y, LOG_Z),
+        kind,
+        Collider::solid(kind.hitbox(), LAYER_OBSTACLE, LAYER_PLAYER),
+        GridObject,
+        GAME_LAYERS,
+    ))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn player(height: f32, is_ducking: bool) -> Player {
+        Player { height, is_ducking, ..default() }
+    }
+
+    #[test]
+    fn a_branch_misses_a_ducking_player() {
+        let branch = ObstacleKind::Branch.height_span();
+        assert!(!branch.overlaps(&HeightSpan::of_player(&player(0., true))));
+        assert!(branch.overlaps(&HeightSpan::of_player(&player(0., false))));
+    }
+
+    #[test]
+    fn a_log_misses_a_player_in_the_air_above_it() {
+        let log = ObstacleKind::Log.height_span();
+        assert!(!log.overlaps(&HeightSpan::of_player(&player(10., false))));
+        assert!(log.overlaps(&HeightSpan::of_player(&player(9., false))));
+    }
+
+    #[test]
+    fn touching_spans_do_not_overlap() {
+        assert!(!HeightSpan::new(0., 10.).overlaps(&HeightSpan::new(10., 20.)));
+        assert!(HeightSpan::new(0., 10.).overlaps(&HeightSpan::new(5., 6.)));
+    }
+}
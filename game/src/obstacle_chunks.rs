@@ -0,0 +1,448 @@
+use bevy::prelude::*;
+use rand::Rng;
+use crate::character::{MIN_JUMP_AIRTIME, PLAYER_HITBOX_SIZE, PLAYER_SPEED};
+use crate::moving_road::{RoadGraph, RoadSpan, ROAD_SHOULDER, ROAD_START_Y};
+use crate::obstacle::{spawn_obstacle, Avoidance, ObstacleKind};
+use crate::world_chunks::{ChunkSpawner, CHUNK_ROWS};
+use crate::world_grid::{MAX_SCROLL_SPEED, TILE_SIZE};
+
+// The first chunk starts just above the top of the screen. Chunks are laid out
+// in world y, measured up from row 0 of the road.
+const FIRST_CHUNK_Y: f32 = 250.0 - ROAD_START_Y;
+const START_SCROLL_SPEED: f32 = 35.0;
+
+// One obstacle in a chunk, `x` is measured from the middle of the road and
+// `y` up from where the chunk starts
+#[derive(Clone, Copy, Debug)]
+pub struct ChunkObstacle {
+    pub kind: ObstacleKind,
+    pub x: f32,
+    pub y: f32,
+}
+
+const fn obstacle(kind: ObstacleKind, x: f32, y: f32) -> ChunkObstacle {
+    ChunkObstacle { kind, x, y }
+}
+
+#[derive(Debug)]
+pub struct ObstacleChunk {
+    pub name: &'static str,
+    pub obstacles: &'static [ChunkObstacle],
+    // Distance from this chunk's start to the next one's
+    pub length: f32,
+    // Chance of being picked is `base_weight + difficulty_weight * difficulty`,
+    // and it isn't picked at all below `min_difficulty`
+    pub base_weight: f32,
+    pub difficulty_weight: f32,
+    pub min_difficulty: f32,
+}
+
+impl ObstacleChunk {
+    pub fn weight(&self, difficulty: f32) -> f32 {
+        if difficulty < self.min_difficulty {
+            return 0.0;
+        }
+        (self.base_weight + self.difficulty_weight * difficulty).max(0.0)
+    }
+}
+
+pub const CHUNKS: &[ObstacleChunk] = &[
+    ObstacleChunk {
+        name: "single log",
+        obstacles: &[obstacle(ObstacleKind::Log, 0.0, 0.0)],
+        length: 160.0,
+        base_weight: 5.0,
+        difficulty_weight: -3.0,
+        min_difficulty: 0.0,
+    },
+    ObstacleChunk {
+        name: "double log",
+        obstacles: &[
+            obstacle(ObstacleKind::Log, 0.0, 0.0),
+            obstacle(ObstacleKind::Log, 0.0, 96.0),
+        ],
+        length: 256.0,
+        base_weight: 1.0,
+        difficulty_weight: 3.0,
+        min_difficulty: 0.1,
+    },
+    ObstacleChunk {
+        name: "branch",
+        obstacles: &[obstacle(ObstacleKind::Branch, 0.0, 0.0)],
+        length: 160.0,
+        base_weight: 2.0,
+        difficulty_weight: 1.0,
+        min_difficulty: 0.0,
+    },
+    ObstacleChunk {
+        name: "log then branch",
+        obstacles: &[
+            obstacle(ObstacleKind::Log, 0.0, 0.0),
+            obstacle(ObstacleKind::Branch, 0.0, 128.0),
+        ],
+        length: 288.0,
+        base_weight: 0.0,
+        difficulty_weight: 3.0,
+        min_difficulty: 0.3,
+    },
+    ObstacleChunk {
+        name: "cactus gauntlet",
+        obstacles: &[
+            obstacle(ObstacleKind::Cactus, -16.0, 0.0),
+            obstacle(ObstacleKind::Cactus, 16.0, 0.0),
+            obstacle(ObstacleKind::Cactus, 48.0, 96.0),
+            obstacle(ObstacleKind::Cactus, -48.0, 192.0),
+            obstacle(ObstacleKind::Cactus, -16.0, 288.0),
+            obstacle(ObstacleKind::Cactus, 16.0, 288.0),
+        ],
+        length: 416.0,
+        base_weight: 0.0,
+        difficulty_weight: 4.0,
+        min_difficulty: 0.2,
+    },
+    ObstacleChunk {
+        name: "gap",
+        obstacles: &[],
+        length: 128.0,
+        base_weight: 1.0,
+        difficulty_weight: 0.0,
+        min_difficulty: 0.0,
+    },
+];
+
+// 0 at the starting speed, 1 at the top speed
+pub fn difficulty(scroll_speed: f32) -> f32 {
+    ((scroll_speed - START_SCROLL_SPEED) / (MAX_SCROLL_SPEED - START_SCROLL_SPEED)).clamp(0.0, 1.0)
+}
+
+// Ranges of x the player can be at, kept sorted and non-overlapping
+type Reachable = Vec<(f32, f32)>;
+
+// Joins ranges that touch, they have to come sorted by their left end
+fn merged(ranges: impl IntoIterator<Item = (f32, f32)>) -> Reachable {
+    let mut merged: Reachable = Vec::new();
+    for (lo, hi) in ranges {
+        match merged.last_mut() {
+            Some(last) if lo <= last.1 => last.1 = last.1.max(hi),
+            _ => merged.push((lo, hi)),
+        }
+    }
+    merged
+}
+
+fn widen(reachable: &Reachable, by: f32) -> Reachable {
+    merged(reachable.iter().map(|&(lo, hi)| (lo - by, hi + by)))
+}
+
+// Where the player can be on a row without running off the road
+fn on_road(spans: &[RoadSpan]) -> Reachable {
+    merged(spans.iter().map(|span| (span.left - ROAD_SHOULDER, span.right + ROAD_SHOULDER)))
+}
+
+fn intersect(a: &Reachable, b: &Reachable) -> Reachable {
+    let mut both: Reachable = Vec::new();
+    for &(a_lo, a_hi) in a {
+        for &(b_lo, b_hi) in b {
+            let (lo, hi) = (a_lo.max(b_lo), a_hi.min(b_hi));
+            if lo < hi {
+                both.push((lo, hi));
+            }
+        }
+    }
+    both.sort_by(|x, y| x.0.total_cmp(&y.0));
+    both
+}
+
+fn cut(reachable: &Reachable, blocked_lo: f32, blocked_hi: f32) -> Reachable {
+    let mut left = Vec::new();
+    for &(lo, hi) in reachable {
+        if hi <= blocked_lo || lo >= blocked_hi {
+            left.push((lo, hi));
+            continue;
+        }
+        if lo < blocked_lo {
+            left.push((lo, blocked_lo));
+        }
+        if hi > blocked_hi {
+            left.push((blocked_hi, hi));
+        }
+    }
+    left
+}
+
+fn road_row(world_y: f32) -> u64 {
+    (world_y / TILE_SIZE).round().max(0.0) as u64
+}
+
+// Whether a player can get through the chunk at this speed without leaving the road,
+// moving sideways at `PLAYER_SPEED` and jumping at most once per `MIN_JUMP_AIRTIME`. Ducking can't
+// happen in the air either. The chunk starts at world y `start_y` and `road` gives the
+// spans of a road row. Obstacles sit on the middle of every span of their row, so they
+// move over with the road where it turns. `ground_run` is how far the previous chunk
+// ended after its last jump or duck.
+pub fn is_passable(
+    chunk: &ObstacleChunk,
+    start_y: f32,
+    road: impl Fn(u64) -> Vec<RoadSpan>,
+    scroll_speed: f32,
+    ground_run: f32,
+) -> bool {
+    let Some(last_row) = chunk.obstacles.iter().map(|obstacle| road_row(start_y + obstacle.y)).max() else {
+        return true;
+    };
+    let jump_distance = MIN_JUMP_AIRTIME * scroll_speed;
+    let move_per_row = PLAYER_SPEED * TILE_SIZE / scroll_speed;
+
+    let first_row = road_row(start_y);
+    let mut reachable = on_road(&road(first_row));
+    // Where the last jump or duck happened, relative to this chunk
+    let mut last_action_y = -ground_run;
+
+    for row in first_row..=last_row {
+        let spans = road(row);
+        if row > first_row {
+            reachable = widen(&reachable, move_per_row);
+        }
+        reachable = intersect(&reachable, &on_road(&spans));
+
+        let obstacles: Vec<&ChunkObstacle> = chunk.obstacles
+            .iter()
+            .filter(|obstacle| road_row(start_y + obstacle.y) == row)
+            .collect();
+        if let Some(row_y) = obstacles.iter().map(|obstacle| obstacle.y).reduce(f32::min) {
+            // A jump or duck needs the player back on the ground first
+            let can_act = row_y - last_action_y >= jump_distance;
+            let mut acted = false;
+
+            for obstacle in obstacles {
+                let needs_dodge = match obstacle.kind.avoidance() {
+                    Avoidance::Dodge => true,
+                    Avoidance::Jump | Avoidance::Duck => !can_act,
+                };
+                if !needs_dodge {
+                    acted = true;
+                    continue;
+                }
+
+                let half_width = (obstacle.kind.hitbox().x + PLAYER_HITBOX_SIZE.x) / 2.0;
+                for span in &spans {
+                    let x = span.middle() + obstacle.x;
+                    reachable = cut(&reachable, x - half_width, x + half_width);
+                }
+            }
+
+            if acted {
+                last_action_y = row_y;
+            }
+        }
+
+        if reachable.is_empty() {
+            return false;
+        }
+    }
+
+    // Room to jump at the start of the next chunk is checked when that one is picked
+    true
+}
+
+#[derive(Resource)]
+pub struct ObstacleGenerator {
+    // World y the next chunk starts at
+    next_y: f32,
+    // Distance since the last jump or duck at the end of the previous chunk
+    ground_run: f32,
+    // Obstacles of picked chunks that reach into world chunks not generated yet,
+    // with their world y
+    pending: Vec<(ChunkObstacle, f32)>,
+}
+
+impl Default for ObstacleGenerator {
+    fn default() -> Self {
+        Self { next_y: FIRST_CHUNK_Y, ground_run: f32::INFINITY, pending: Vec::new() }
+    }
+}
+
+fn pick_chunk(
+    rng: &mut impl Rng,
+    start_y: f32,
+    road: impl Fn(u64) -> Vec<RoadSpan>,
+    scroll_speed: f32,
+    ground_run: f32,
+) -> &'static ObstacleChunk {
+    let difficulty = difficulty(scroll_speed);
+    let candidates: Vec<(&ObstacleChunk, f32)> = CHUNKS
+        .iter()
+        .filter(|chunk| is_passable(chunk, start_y, &road, scroll_speed, ground_run))
+        .map(|chunk| (chunk, chunk.weight(difficulty)))
+        .filter(|(_, weight)| *weight > 0.0)
+        .collect();
+
+    let total: f32 = candidates.iter().map(|(_, weight)| weight).sum();
+    let mut roll = rng.gen_range(0.0..total.max(f32::EPSILON));
+    for (chunk, weight) in &candidates {
+        if roll < *weight {
+            return chunk;
+        }
+        roll -= weight;
+    }
+
+    // Nothing fits (or rounding), an empty stretch always does
+    CHUNKS.iter().find(|chunk| chunk.obstacles.is_empty()).unwrap()
+}
+
+pub fn setup_obstacle_generator(mut commands: Commands) {
+    commands.insert_resource(ObstacleGenerator::default());
+}
+
+// Obstacle stage of the chunk pipeline. Picks obstacle chunks until they
+// reach past this world chunk and places whatever lands in it.
+pub fn generate_obstacles(
+    spawner: &mut ChunkSpawner,
+    generator: &mut ObstacleGenerator,
+    road_graph: &RoadGraph,
+    scroll_speed: f32,
+    rng: &mut impl Rng,
+) {
+    let first_row = spawner.chunk.first_row;
+    // Rows are centred on multiples of a tile, so the world chunk ends half a tile above its top row
+    let end_y = ((first_row + CHUNK_ROWS as u64) as f32 - 0.5) * TILE_SIZE;
+
+    // The road stage has generated the rows past this chunk as well
+    let road = |row: u64| match row.checked_sub(first_row) {
+        Some(row) => spawner.chunk.spans(row as u32),
+        None => Vec::new(),
+    };
+
+    while generator.next_y < end_y {
+        let chunk = pick_chunk(rng, generator.next_y, road, scroll_speed, generator.ground_run);
+        for &obstacle in chunk.obstacles {
+            generator.pending.push((obstacle, generator.next_y + obstacle.y));
+        }
+
+        let last_action_y = chunk.obstacles
+            .iter()
+            .filter(|obstacle| obstacle.kind.avoidance() != Avoidance::Dodge)
+            .map(|obstacle| obstacle.y)
+            .reduce(f32::max);
+        generator.ground_run = match last_action_y {
+            Some(y) => chunk.length - y,
+            None => generator.ground_run + chunk.length,
+        };
+        generator.next_y += chunk.length;
+    }
+
+    let (here, later): (Vec<_>, Vec<_>) = generator.pending.drain(..).partition(|&(_, y)| y < end_y);
+    generator.pending = later;
+
+    for (obstacle, world_y) in here {
+        let row = road_row(world_y).saturating_sub(first_row) as u32;
+        let y = spawner.chunk.bottom_y + (world_y - first_row as f32 * TILE_SIZE).floor();
+
+        // On every branch, thinned out on the quieter ones. Leaving obstacles
+        // out never makes a chunk impassable.
+        for span in spawner.chunk.spans(row) {
+            if !rng.gen_bool(road_graph.profile(span.segment).obstacle_chance) {
+                continue;
+            }
+            let position = Vec2::new(span.middle() + obstacle.x, y);
+            spawn_obstacle(spawner, obstacle.kind, position);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::moving_road::{row_spans, RoadRowTile, ROAD_HALF_WIDTH, ROAD_UP};
+    use crate::world_grid::GRID_SIZE_X;
+
+    fn chunk(obstacles: Vec<ChunkObstacle>) -> ObstacleChunk {
+        ObstacleChunk {
+            name: "test",
+            obstacles: obstacles.leak(),
+            length: 512.0,
+            base_weight: 1.0,
+            difficulty_weight: 0.0,
+            min_difficulty: 0.0,
+        }
+    }
+
+    // Obstacles side by side across the whole road at height `y`
+    fn wall(kind: ObstacleKind, y: f32) -> Vec<ChunkObstacle> {
+        let step = kind.hitbox().x;
+        let mut x = -ROAD_HALF_WIDTH + step / 2.0;
+        let mut row = Vec::new();
+        while x - step / 2.0 < ROAD_HALF_WIDTH {
+            row.push(obstacle(kind, x, y));
+            x += step;
+        }
+        row
+    }
+
+    fn road_tile(column: u32) -> RoadRowTile {
+        RoadRowTile { column, exits: ROAD_UP, segment: 0 }
+    }
+
+    // A road one tile wide down the middle of the screen
+    fn straight(_row: u64) -> Vec<RoadSpan> {
+        row_spans(&[road_tile(GRID_SIZE_X / 2)])
+    }
+
+    // The same road, moving one column to the right on row `turn`
+    fn turning_right_at(turn: u64) -> impl Fn(u64) -> Vec<RoadSpan> {
+        move |row| {
+            let middle = GRID_SIZE_X / 2;
+            match row.cmp(&turn) {
+                std::cmp::Ordering::Less => row_spans(&[road_tile(middle)]),
+                std::cmp::Ordering::Equal => row_spans(&[road_tile(middle), road_tile(middle + 1)]),
+                std::cmp::Ordering::Greater => row_spans(&[road_tile(middle + 1)]),
+            }
+        }
+    }
+
+    #[test]
+    fn every_chunk_that_can_be_picked_is_passable() {
+        for speed in [START_SCROLL_SPEED, MAX_SCROLL_SPEED] {
+            for chunk in CHUNKS.iter().filter(|chunk| chunk.weight(difficulty(speed)) > 0.0) {
+                assert!(is_passable(chunk, 0.0, straight, speed, f32::INFINITY), "'{}' at {}", chunk.name, speed);
+            }
+        }
+    }
+
+    #[test]
+    fn a_wall_of_cacti_is_not_passable() {
+        let chunk = chunk(wall(ObstacleKind::Cactus, 0.0));
+        assert!(!is_passable(&chunk, 0.0, straight, START_SCROLL_SPEED, f32::INFINITY));
+    }
+
+    #[test]
+    fn back_to_back_jumps_need_time_on_the_ground() {
+        let speed = MAX_SCROLL_SPEED;
+        let jump_distance = MIN_JUMP_AIRTIME * speed;
+        let two_walls = |gap: f32| {
+            let mut obstacles = wall(ObstacleKind::Log, 0.0);
+            obstacles.extend(wall(ObstacleKind::Log, gap));
+            chunk(obstacles)
+        };
+
+        assert!(is_passable(&two_walls(jump_distance), 0.0, straight, speed, f32::INFINITY));
+        assert!(!is_passable(&two_walls(jump_distance * 0.9), 0.0, straight, speed, f32::INFINITY));
+        // Right after a jump at the end of the previous chunk, not even one fits
+        assert!(!is_passable(&two_walls(jump_distance), 0.0, straight, speed, jump_distance * 0.5));
+    }
+
+    #[test]
+    fn obstacles_move_over_with_the_road() {
+        // Cacti leave a gap on the far left only, then a log a few rows later
+        let mut obstacles: Vec<ChunkObstacle> = (0..5)
+            .map(|i| obstacle(ObstacleKind::Cactus, -28.0 + 20.0 * i as f32, 0.0))
+            .collect();
+        obstacles.push(obstacle(ObstacleKind::Log, 0.0, 96.0));
+        let chunk = chunk(obstacles);
+
+        assert!(is_passable(&chunk, 0.0, straight, MAX_SCROLL_SPEED, f32::INFINITY));
+        // The road moves away from the gap faster than the player can follow
+        assert!(!is_passable(&chunk, 0.0, turning_right_at(1), MAX_SCROLL_SPEED, f32::INFINITY));
+        assert!(is_passable(&chunk, 0.0, turning_right_at(1), START_SCROLL_SPEED, f32::INFINITY));
+    }
+}
@@ -0,0 +1,318 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use rand::Rng;
+use crate::background::GAME_LAYERS;
+use crate::character::Player;
+use crate::collision::{Collider, CollisionEvent, CollisionKind, LAYER_PICKUP, LAYER_PLAYER};
+use crate::moving_road::{RoadGraph, ROAD_SHOULDER};
+use crate::pool::{EntityPool, Parked};
+use crate::scoreboard::ScoreText;
+use crate::spatial_grid::SpatialGrid;
+use crate::world_chunks::{ChunkSpawner, CHUNK_ROWS};
+use crate::world_grid::{GridObject, LOG_Z, TILE_SIZE};
+
+const COIN_SCORE: f32 = 50.0;
+const POOL_KEY: &str = "pickup";
+// Coins in a row are half a tile apart
+const COIN_SPACING: f32 = TILE_SIZE / 2.0;
+// Tile rows the longest row of coins reaches over, it has to fit in its chunk
+const PICKUP_ROWS: u32 = 3;
+// Seconds of scrolling between pickups, on average
+const PICKUP_INTERVAL_SECS: f32 = 1.5;
+// Coins and power-ups land on the road or its shoulder
+const PICKUP_SPREAD_X: f32 = ROAD_SHOULDER;
+
+// Power-up durations, in game time
+const SHIELD_SECS: f32 = 10.0;
+const MAGNET_SECS: f32 = 8.0;
+const SLOW_MOTION_SECS: f32 = 5.0;
+const SLOW_MOTION_SPEED: f32 = 0.6;
+const MAGNET_RADIUS: f32 = 96.0;
+const MAGNET_PULL_SPEED: f32 = 180.0;
+
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pickup {
+    Coin,
+    Shield,
+    Magnet,
+    SlowMotion,
+}
+
+impl Pickup {
+    fn color(self) -> Color {
+        match self {
+            Pickup::Coin => Color::srgb(1.0, 0.85, 0.1),
+            Pickup::Shield => Color::srgb(0.3, 0.6, 1.0),
+            Pickup::Magnet => Color::srgb(0.9, 0.2, 0.3),
+            Pickup::SlowMotion => Color::srgb(0.6, 0.3, 0.9),
+        }
+    }
+
+    fn size(self) -> Vec2 {
+        match self {
+            Pickup::Coin => Vec2::splat(8.0),
+            _ => Vec2::splat(12.0),
+        }
+    }
+}
+
+// Time left on each power-up, None when it isn't active
+#[derive(Resource, Default)]
+pub struct ActivePowerUps {
+    pub shield: Option<Timer>,
+    pub magnet: Option<Timer>,
+    pub slow_motion: Option<Timer>,
+}
+
+impl ActivePowerUps {
+    // Uses up the shield, if there is one, instead of a life
+    pub fn consume_shield(&mut self) -> bool {
+        self.shield.take().is_some()
+    }
+}
+
+#[derive(Component)]
+pub struct PowerUpDisplay;
+
+pub fn setup_pickups(mut commands: Commands, mut time: ResMut<Time<Virtual>>) {
+    commands.insert_resource(ActivePowerUps::default());
+    // A run that ended in slow motion shouldn't start the next one slowed down
+    time.set_relative_speed(1.0);
+
+    commands.spawn((
+        Text::new(""),
+        TextFont {
+            font_size: 16.0,
+            ..default()
+        },
+        TextColor(Color::srgb(0.9, 0.9, 0.5)),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(28.0),
+            right: Val::Px(0.0),
+            ..default()
+        },
+        PowerUpDisplay,
+    ));
+}
+
+fn spawn_pickup(spawner: &mut ChunkSpawner, kind: Pickup, position: Vec2) {
+    spawner.spawn(POOL_KEY, (
+        Sprite {
+            color: kind.color(),
+            custom_size: Some(kind.size()),
+            ..default()
+        },
+        Transform::from_xyz(position.x, position.y, LOG_Z),
+        kind,
+        Collider::trigger(kind.size(), LAYER_PICKUP, LAYER_PLAYER),
+        GridObject,
+        GAME_LAYERS,
+    ));
+}
+
+// Pickup stage of the chunk pipeline. On average one pickup or row of coins
+// scrolls in every PICKUP_INTERVAL_SECS, however fast the world is moving.
+pub fn generate_pickups(
+    spawner: &mut ChunkSpawner,
+    road_graph: &RoadGraph,
+    scroll_speed: f32,
+    rng: &mut impl Rng,
+) {
+    let count = pickups_per_chunk(scroll_speed, rng);
+    // Rows of coins start low enough to end inside the chunk
+    let band = (CHUNK_ROWS - PICKUP_ROWS - 1) as f32 * TILE_SIZE;
+
+    // Spread out over the chunk, so they don't end up on top of each other
+    for slot in 0..count {
+        let offset = ((slot as f32 + rng.gen::<f32>()) / count as f32 * band).floor();
+        generate_pickup(spawner, road_graph, offset, rng);
+    }
+}
+
+// A chunk takes `CHUNK_ROWS * TILE_SIZE / scroll_speed` seconds to scroll past,
+// the fraction of a pickup left over is rounded up at random
+fn pickups_per_chunk(scroll_speed: f32, rng: &mut impl Rng) -> u32 {
+    // The world stops when the player dies, and a chunk that never passes gets nothing
+    if scroll_speed <= 0.0 {
+        return 0;
+    }
+    let per_chunk = CHUNK_ROWS as f32 * TILE_SIZE / (scroll_speed * PICKUP_INTERVAL_SECS);
+    per_chunk.floor() as u32 + rng.gen_bool(per_chunk.fract() as f64) as u32
+}
+
+// A pickup or row of coins `offset` above the chunk's bottom row
+fn generate_pickup(spawner: &mut ChunkSpawner, road_graph: &RoadGraph, offset: f32, rng: &mut impl Rng) {
+    let row = (offset / TILE_SIZE).round() as u32;
+    let spans = spawner.chunk.spans(row);
+    if spans.is_empty() {
+        return;
+    }
+
+    // Any branch of the road, when it's split
+    let span = spans[rng.gen_range(0..spans.len())];
+    let x = (span.middle() + rng.gen_range(-PICKUP_SPREAD_X..PICKUP_SPREAD_X)).floor();
+    let y = spawner.chunk.row_y(0) + offset;
+
+    // Mostly a short row of coins, sometimes a power-up
+    let kind = match rng.gen_range(0..10) {
+        0 => Pickup::Shield,
+        1 => Pickup::Magnet,
+        2 => Pickup::SlowMotion,
+        _ => Pickup::Coin,
+    };
+
+    // Risky branches have longer rows of coins
+    if kind == Pickup::Coin {
+        for i in 0..road_graph.profile(span.segment).coins_per_row {
+            spawn_pickup(spawner, kind, Vec2::new(x, y + i as f32 * COIN_SPACING));
+        }
+    } else {
+        spawn_pickup(spawner, kind, Vec2::new(x, y));
+    }
+}
+
+pub fn collect_pickups(
+    mut commands: Commands,
+    mut collision_events: EventReader<CollisionEvent>,
+    player_query: Query<Entity, With<Player>>,
+    pickup_query: Query<&Pickup, Without<Parked>>,
+    mut score_text: ResMut<ScoreText>,
+    mut power_ups: ResMut<ActivePowerUps>,
+    mut pool: ResMut<EntityPool>,
+) {
+    for event in collision_events.read() {
+        if event.kind != CollisionKind::Trigger {
+            continue;
+        }
+
+        for player in player_query.iter() {
+            let Some(other) = event.other(player) else {
+                continue;
+            };
+            let Ok(&kind) = pickup_query.get(other) else {
+                continue;
+            };
+
+            match kind {
+                Pickup::Coin => score_text.score += COIN_SCORE,
+                Pickup::Shield => power_ups.shield = Some(Timer::from_seconds(SHIELD_SECS, TimerMode::Once)),
+                Pickup::Magnet => power_ups.magnet = Some(Timer::from_seconds(MAGNET_SECS, TimerMode::Once)),
+                Pickup::SlowMotion => power_ups.slow_motion = Some(Timer::from_seconds(SLOW_MOTION_SECS, TimerMode::Once)),
+            }
+            pool.park(&mut commands, other, POOL_KEY);
+        }
+    }
+}
+
+pub fn update_power_ups(
+    time: Res<Time>,
+    mut power_ups: ResMut<ActivePowerUps>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+) {
+    let delta = time.delta();
+    let tick = |slot: &mut Option<Timer>, delta: Duration| {
+        if let Some(timer) = slot {
+            if timer.tick(delta).finished() {
+                *slot = None;
+            }
+        }
+    };
+
+    tick(&mut power_ups.shield, delta);
+    tick(&mut power_ups.magnet, delta);
+
+    tick(&mut power_ups.slow_motion, delta);
+    let speed = if power_ups.slow_motion.is_some() { SLOW_MOTION_SPEED } else { 1.0 };
+    if virtual_time.relative_speed() != speed {
+        virtual_time.set_relative_speed(speed);
+    }
+}
+
+// Pulls nearby coins towards the player while the magnet is active
+pub fn attract_coins(
+    time: Res<Time>,
+    power_ups: Res<ActivePowerUps>,
+    player_query: Query<&Transform, With<Player>>,
+    mut coin_query: Query<(Entity, &Pickup, &Collider, &mut Transform), Without<Player>>,
+    mut spatial_grid: ResMut<SpatialGrid>,
+) {
+    if power_ups.magnet.is_none() {
+        return;
+    }
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
+    };
+    let player_pos = player_transform.translation.truncate();
+
+    for (entity, pickup, collider, mut transform) in coin_query.iter_mut() {
+        if *pickup != Pickup::Coin {
+            continue;
+        }
+
+        let offset = player_pos - transform.translation.truncate();
+        if offset.length() > MAGNET_RADIUS {
+            continue;
+        }
+
+        let step = offset.clamp_length_max(MAGNET_PULL_SPEED * time.delta_secs());
+        transform.translation += step.extend(0.0);
+        // Coins scroll with the grid, so the broadphase only hears about this from us
+        spatial_grid.insert(entity, transform.translation.truncate(), collider.size);
+    }
+}
+
+pub fn update_power_up_display(
+    power_ups: Res<ActivePowerUps>,
+    mut query: Query<&mut Text, With<PowerUpDisplay>>,
+) {
+    if !power_ups.is_changed() {
+        return;
+    }
+
+    let active = [
+        ("Shield", &power_ups.shield),
+        ("Magnet", &power_ups.magnet),
+        ("Slow-mo", &power_ups.slow_motion),
+    ];
+    let text = active
+        .iter()
+        .filter_map(|(name, timer)| {
+            timer.as_ref().map(|timer| format!("{} {:.0}s", name, timer.remaining_secs().ceil()))
+        })
+        .collect::<Vec<_>>()
+        .join("  ");
+
+    for mut display in query.iter_mut() {
+        display.0 = text.clone();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+    use crate::world_grid::MAX_SCROLL_SPEED;
+
+    #[test]
+    fn pickups_keep_their_rate_at_any_speed() {
+        let mut rng = ChaCha8Rng::seed_from_u64(25);
+        for speed in [35.0, 120.0, MAX_SCROLL_SPEED] {
+            let chunks = 10_000;
+            let pickups: u32 = (0..chunks).map(|_| pickups_per_chunk(speed, &mut rng)).sum();
+            let seconds = chunks as f32 * CHUNK_ROWS as f32 * TILE_SIZE / speed;
+
+            let interval = seconds / pickups as f32;
+            assert!((interval - PICKUP_INTERVAL_SECS).abs() < 0.05, "one every {}s at {}", interval, speed);
+        }
+    }
+
+    #[test]
+    fn no_pickups_when_the_world_stops() {
+        let mut rng = ChaCha8Rng::seed_from_u64(25);
+        assert_eq!(pickups_per_chunk(0.0, &mut rng), 0);
+        assert_eq!(pickups_per_chunk(-1.0, &mut rng), 0);
+    }
+}
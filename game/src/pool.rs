@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use crate::collision::Collider;
+use crate::world_chunks::ChunkMember;
+use crate::world_grid::{GridObject, GRID_SIZE_Y, TILE_SIZE};
+
+// Scrolling objects are recycled once they're completely below the screen.
+// World chunks retire on this line too, with their road rows. Anything outside
+// a chunk, like the debug grid, is recycled on its own by recycle_grid_objects.
+pub const RECYCLE_Y: f32 = -(GRID_SIZE_Y as f32) * TILE_SIZE / 2.0 - TILE_SIZE;
+
+// A GridObject that's parked in the EntityPool under this key when it leaves the bottom.
+// Everything under one key has to be spawned with the same components, since
+// the next object taken from the pool only overwrites them.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Pooled(pub &'static str);
+
+// On an entity waiting in the pool. It keeps the components of what it was last,
+// so queries on those have to leave it out.
+#[derive(Component)]
+pub struct Parked;
+
+// Entities that scrolled away, kept for the next spawn instead of being despawned
+#[derive(Resource, Default)]
+pub struct EntityPool {
+    parked: HashMap<&'static str, Vec<Entity>>,
+    // Parked this frame, their commands haven't been applied yet
+    parking: Vec<(&'static str, Entity)>,
+}
+
+impl EntityPool {
+    // An entity to spawn a `key` object into, a parked one if there is one.
+    // Insert the object's components on it either way.
+    pub fn take<'a>(&mut self, commands: &'a mut Commands, key: &'static str) -> EntityCommands<'a> {
+        match self.parked.get_mut(key).and_then(Vec::pop) {
+            Some(entity) => {
+                let mut entity_commands = commands.entity(entity);
+                entity_commands.insert(Visibility::Inherited).remove::<Parked>();
+                entity_commands
+            }
+            None => commands.spawn(Pooled(key)),
+        }
+    }
+
+    // Hides the entity and stops it scrolling and colliding until it's taken again.
+    // Parking an entity twice in one frame does nothing the second time.
+    pub fn park(&mut self, commands: &mut Commands, entity: Entity, key: &'static str) {
+        if self.parking.iter().any(|&(_, parking)| parking == entity) {
+            return;
+        }
+        commands.entity(entity)
+            .insert((Visibility::Hidden, Parked))
+            .remove::<(GridObject, Collider, ChunkMember)>();
+        self.parking.push((key, entity));
+    }
+
+    pub fn parked(&self, key: &'static str) -> usize {
+        self.parked.get(key).map_or(0, Vec::len)
+    }
+
+    // Entities parked last frame can be taken from now on
+    fn settle(&mut self) {
+        for (key, entity) in self.parking.drain(..) {
+            self.parked.entry(key).or_default().push(entity);
+        }
+    }
+}
+
+pub fn setup_entity_pool(mut commands: Commands) {
+    // Whatever was parked was despawned with the rest of the last run
+    commands.insert_resource(EntityPool::default());
+}
+
+// Scrolling objects that aren't part of a world chunk
+type Unchunked = (With<GridObject>, Without<ChunkMember>);
+
+// World chunks retire their own members, anything else that scrolls away
+// is parked here, or despawned if it isn't pooled
+pub fn recycle_grid_objects(
+    mut commands: Commands,
+    mut pool: ResMut<EntityPool>,
+    query: Query<(Entity, &Transform, Option<&Pooled>), Unchunked>,
+) {
+    pool.settle();
+
+    for (entity, transform, pooled) in query.iter() {
+        if transform.translation.y >= RECYCLE_Y {
+            continue;
+        }
+
+        match pooled {
+            Some(&Pooled(key)) => pool.park(&mut commands, entity, key),
+            None => commands.entity(entity).despawn(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::world::CommandQueue;
+
+    const KEY: &str = "test";
+
+    fn with_commands(world: &mut World, f: impl FnOnce(&mut Commands)) {
+        let mut queue = CommandQueue::default();
+        f(&mut Commands::new(&mut queue, world));
+        queue.apply(world);
+    }
+
+    #[test]
+    fn parking_twice_in_a_frame_pools_the_entity_once() {
+        let mut world = World::new();
+        let mut pool = EntityPool::default();
+        let entity = world.spawn((Pooled(KEY), GridObject, ChunkMember(0))).id();
+
+        with_commands(&mut world, |commands| {
+            pool.park(commands, entity, KEY);
+            pool.park(commands, entity, KEY);
+        });
+        pool.settle();
+
+        assert_eq!(pool.parked(KEY), 1);
+        assert!(world.get::<Parked>(entity).is_some());
+        assert!(world.get::<GridObject>(entity).is_none());
+        assert!(world.get::<ChunkMember>(entity).is_none());
+    }
+
+    #[test]
+    fn taking_an_entity_unparks_it() {
+        let mut world = World::new();
+        let mut pool = EntityPool::default();
+        let entity = world.spawn(Pooled(KEY)).id();
+
+        with_commands(&mut world, |commands| pool.park(commands, entity, KEY));
+        pool.settle();
+        with_commands(&mut world, |commands| {
+            assert_eq!(pool.take(commands, KEY).id(), entity);
+        });
+
+        assert_eq!(pool.parked(KEY), 0);
+        assert!(world.get::<Parked>(entity).is_none());
+        assert_eq!(world.get::<Visibility>(entity), Some(&Visibility::Inherited));
+    }
+}
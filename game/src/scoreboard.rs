@@ -1,13 +1,20 @@
 use bevy::prelude::*;
 use crate::character::Player;
 use crate::world_grid::GridConfig;
+use crate::input::{Action, ActionState};
+use crate::microbit::{BoardMessage, SerialOutbox};
+use crate::world_seed::WorldSeed;
+use crate::daily::GameMode;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::io::Write;
 use bevy::input::keyboard::KeyboardInput;
 use bevy::input::ButtonState;
 
 const HIGHSCORE_FILE: &str = "highscores.json";
+// The micro:bit scrolls the score every time it passes a multiple of this
+const SCORE_MILESTONE: f32 = 100.0;
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct HighScoreEntry {
@@ -15,28 +22,71 @@ pub struct HighScoreEntry {
     pub score: f32,
 }
 
+const MAX_ENTRIES: usize = 10;
+
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct DailyBoard {
+    pub scores: Vec<HighScoreEntry>,
+    // The day's one scored run has been started
+    pub attempted: bool,
+}
+
 #[derive(Resource, Serialize, Deserialize, Default, Debug)]
 pub struct HighScores {
     pub scores: Vec<HighScoreEntry>,
+    // Daily run leaderboards by date (YYYY-MM-DD)
+    #[serde(default)]
+    pub daily: BTreeMap<String, DailyBoard>,
+}
+
+fn qualifies(scores: &[HighScoreEntry], score: f32) -> bool {
+    if scores.len() < MAX_ENTRIES {
+        return true;
+    }
+    score > scores.last().map(|s| s.score).unwrap_or(0.0)
+}
+
+fn insert_score(scores: &mut Vec<HighScoreEntry>, name: String, score: f32) {
+    scores.push(HighScoreEntry { name, score });
+    // Sort descending
+    scores.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+    // Keep top 10
+    scores.truncate(MAX_ENTRIES);
 }
 
 impl HighScores {
     pub fn is_high_score(&self, score: f32) -> bool {
-        if self.scores.len() < 10 {
-            return true;
-        }
-        score > self.scores.last().map(|s| s.score).unwrap_or(0.0)
+        qualifies(&self.scores, score)
     }
 
     pub fn add_score(&mut self, name: String, score: f32) {
-        self.scores.push(HighScoreEntry { name, score });
-        // Sort descending
-        self.scores.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
-        // Keep top 10
-        if self.scores.len() > 10 {
-            self.scores.truncate(10);
+        insert_score(&mut self.scores, name, score);
+    }
+
+    // The leaderboard the current mode plays for
+    pub fn board(&self, mode: &GameMode) -> &[HighScoreEntry] {
+        match mode.daily_date() {
+            Some(date) => self.daily.get(date).map(|board| board.scores.as_slice()).unwrap_or_default(),
+            None => &self.scores,
         }
     }
+
+    pub fn is_high_score_for(&self, mode: &GameMode, score: f32) -> bool {
+        mode.is_scored() && qualifies(self.board(mode), score)
+    }
+
+    pub fn add_score_for(&mut self, mode: &GameMode, name: String, score: f32) {
+        match mode.daily_date() {
+            Some(date) => insert_score(&mut self.daily.entry(date.to_string()).or_default().scores, name, score),
+            None => self.add_score(name, score),
+        }
+    }
+
+    // Marks the day's attempt as taken, false if it already was
+    pub fn start_daily_attempt(&mut self, date: &str) -> bool {
+        let board = self.daily.entry(date.to_string()).or_default();
+        !std::mem::replace(&mut board.attempted, true)
+    }
 }
 
 #[derive(Resource, Default)]
@@ -51,6 +101,10 @@ pub struct FinalScore(pub f32);
 #[derive(Resource, Default)]
 pub struct PlayerNameInput(pub String);
 
+// Last milestone announced on the micro:bit
+#[derive(Resource, Default)]
+pub struct ScoreMilestone(pub u32);
+
 #[derive(Resource, Default, PartialEq, Eq, Clone, Copy, Debug)]
 pub enum ScoreboardState {
     #[default]
@@ -75,13 +129,26 @@ pub struct DeathScoreDisplay;
 #[derive(Resource)]
 pub struct ScoreTimer(pub Timer);
 
-pub fn setup_scoreboard(mut commands: Commands, mut score_text: ResMut<ScoreText>) {
+pub fn setup_scoreboard(
+    mut commands: Commands,
+    mut score_text: ResMut<ScoreText>,
+    mut mode: ResMut<GameMode>,
+) {
     // Load high scores
-    let high_scores = load_high_scores().unwrap_or_default();
+    let mut high_scores = load_high_scores().unwrap_or_default();
+
+    // Saved right away, so quitting halfway through doesn't give a second try
+    if let GameMode::Daily { date, scored, .. } = &mut *mode {
+        *scored = high_scores.start_daily_attempt(date);
+        if *scored {
+            save_high_scores(&high_scores).ok();
+        }
+    }
     commands.insert_resource(high_scores);
     commands.insert_resource(ScoreboardState::Hidden);
     commands.insert_resource(PlayerNameInput("".to_string()));
     commands.insert_resource(FinalScore(0.0));
+    commands.insert_resource(ScoreMilestone(0));
     
     // Reset score
     score_text.score = 0.0;
@@ -116,6 +183,19 @@ pub fn increase_score_system(
     }
 }
 
+pub fn announce_score_milestones(
+    score_text: Res<ScoreText>,
+    mut milestone: ResMut<ScoreMilestone>,
+    outbox: Res<SerialOutbox>,
+) {
+    let reached = (score_text.score / SCORE_MILESTONE).floor() as u32;
+
+    if reached > milestone.0 {
+        milestone.0 = reached;
+        outbox.send(BoardMessage::Score(reached * SCORE_MILESTONE as u32));
+    }
+}
+
 pub fn scoreboard_system(
     mut score: ResMut<ScoreText>,
     mut display_query: Query<&mut Text, With<ScoreDisplay>>,
@@ -140,6 +220,8 @@ pub fn update_death_scoreboard_ui(
     high_scores: Res<HighScores>,
     name_input: Res<PlayerNameInput>,
     final_score: Res<FinalScore>,
+    world_seed: Res<WorldSeed>,
+    mode: Res<GameMode>,
     ui_query: Query<Entity, With<DeathScoreDisplay>>,
 ) {
     if *state != *current_state {
@@ -153,7 +235,7 @@ pub fn update_death_scoreboard_ui(
                 spawn_enter_name_ui(&mut commands, final_score.0, &name_input.0);
             },
             ScoreboardState::ShowScores => {
-                spawn_high_score_list(&mut commands, &high_scores, final_score.0);
+                spawn_high_score_list(&mut commands, &high_scores, &mode, final_score.0, world_seed.seed);
             },
             ScoreboardState::Hidden => {},
         }
@@ -188,21 +270,23 @@ pub fn handle_input_system(
     mut commands: Commands,
     mut key_evr: EventReader<KeyboardInput>,
     keys: Res<ButtonInput<KeyCode>>,
+    actions: Res<ActionState>,
     mut name_input: ResMut<PlayerNameInput>,
     mut state: ResMut<ScoreboardState>,
     mut high_scores: ResMut<HighScores>,
     final_score: Res<FinalScore>,
+    mode: Res<GameMode>,
     mut next_game_state: ResMut<NextState<GameState>>,
 ) {
     if *state == ScoreboardState::EnterName {
-        if keys.just_pressed(KeyCode::Enter) {
+        if actions.just_pressed(Action::Confirm) {
             let name = if name_input.0.trim().is_empty() {
                 "Anonymous".to_string()
             } else {
                 name_input.0.clone()
             };
             
-            high_scores.add_score(name, final_score.0);
+            high_scores.add_score_for(&mode, name, final_score.0);
             save_high_scores(&high_scores).ok();
             *state = ScoreboardState::ShowScores;
             return;
@@ -229,7 +313,7 @@ pub fn handle_input_system(
             }
         }
     } else if *state == ScoreboardState::ShowScores {
-         if keys.just_pressed(KeyCode::KeyR) {
+         if actions.just_pressed(Action::Restart) {
              next_game_state.set(GameState::Playing);
          }
     }
@@ -264,7 +348,18 @@ fn spawn_enter_name_ui(commands: &mut Commands, score: f32, current_name: &str)
     });
 }
 
-fn spawn_high_score_list(commands: &mut Commands, high_scores: &HighScores, user_score: f32) {
+fn spawn_high_score_list(
+    commands: &mut Commands,
+    high_scores: &HighScores,
+    mode: &GameMode,
+    user_score: f32,
+    seed: u64,
+) {
+    let title = match mode.daily_date() {
+        Some(date) => format!("DAILY RUN {}", date),
+        None => "HIGH SCORES".to_string(),
+    };
+
     commands.spawn((
         Node {
             width: Val::Percent(100.0),
@@ -279,13 +374,22 @@ fn spawn_high_score_list(commands: &mut Commands, high_scores: &HighScores, user
         DeathScoreDisplay
     )).with_children(|parent| {
         parent.spawn((
-            Text::new("HIGH SCORES"),
+            Text::new(title),
             TextFont { font_size: 50.0, ..default() },
             TextColor(Color::srgb(1.0, 0.84, 0.0)),
             Node { margin: UiRect::bottom(Val::Px(20.0)), ..default() },
         ));
 
-        for (i, entry) in high_scores.scores.iter().enumerate() {
+        if !mode.is_scored() {
+            parent.spawn((
+                Text::new(format!("Practice run ({:.0}), today's scored run is used", user_score)),
+                TextFont { font_size: 20.0, ..default() },
+                TextColor(Color::srgb(0.7, 0.7, 0.7)),
+                Node { margin: UiRect::bottom(Val::Px(20.0)), ..default() },
+            ));
+        }
+
+        for (i, entry) in high_scores.board(mode).iter().enumerate() {
             let color = if entry.score == user_score { Color::srgb(0.0, 1.0, 0.0) } else { Color::WHITE };
             parent.spawn((
                 Text::new(format!("{}. {} - {:.0}", i + 1, entry.name, entry.score)),
@@ -294,6 +398,14 @@ fn spawn_high_score_list(commands: &mut Commands, high_scores: &HighScores, user
             ));
         }
 
+        // Start with --seed <n> to run the same world again
+        parent.spawn((
+            Text::new(format!("Seed: {}", seed)),
+            TextFont { font_size: 20.0, ..default() },
+            TextColor(Color::srgb(0.7, 0.7, 0.7)),
+            Node { margin: UiRect::top(Val::Px(20.0)), ..default() },
+        ));
+
         parent.spawn((
              Text::new("Press R to Restart"), 
              TextFont { font_size: 20.0, ..default() },
@@ -318,3 +430,37 @@ fn save_high_scores(scores: &HighScores) -> std::io::Result<()> {
     file.write_all(data.as_bytes())?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn daily(date: &str) -> GameMode {
+        GameMode::Daily { day: 0, date: date.to_string(), scored: true }
+    }
+
+    #[test]
+    fn only_the_first_daily_attempt_counts() {
+        let mut high_scores = HighScores::default();
+        assert!(high_scores.start_daily_attempt("2000-02-29"));
+        assert!(!high_scores.start_daily_attempt("2000-02-29"));
+
+        // Another day gets its own attempt
+        assert!(high_scores.start_daily_attempt("2000-03-01"));
+        assert!(!high_scores.start_daily_attempt("2000-02-29"));
+    }
+
+    #[test]
+    fn daily_scores_go_on_the_days_board() {
+        let mut high_scores = HighScores::default();
+        high_scores.add_score_for(&daily("2000-02-29"), "ada".to_string(), 42.0);
+
+        assert!(high_scores.scores.is_empty());
+        assert_eq!(high_scores.board(&daily("2000-02-29"))[0].name, "ada");
+        assert!(high_scores.board(&daily("2000-03-01")).is_empty());
+
+        high_scores.add_score_for(&GameMode::Normal, "bo".to_string(), 7.0);
+        assert_eq!(high_scores.board(&GameMode::Normal)[0].name, "bo");
+        assert_eq!(high_scores.board(&daily("2000-02-29")).len(), 1);
+    }
+}
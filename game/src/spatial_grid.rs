@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use crate::world_grid::TILE_SIZE;
+
+// The cells an AABB covers, both ends inclusive
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct CellRange {
+    min: IVec2,
+    max: IVec2,
+}
+
+impl CellRange {
+    fn of(position: Vec2, size: Vec2) -> Self {
+        let half = size / 2.0;
+        Self {
+            min: cell_of(position - half),
+            max: cell_of(position + half),
+        }
+    }
+
+    fn cells(self) -> impl Iterator<Item = IVec2> {
+        (self.min.y..=self.max.y)
+            .flat_map(move |y| (self.min.x..=self.max.x).map(move |x| IVec2::new(x, y)))
+    }
+}
+
+fn cell_of(position: Vec2) -> IVec2 {
+    (position / TILE_SIZE).floor().as_ivec2()
+}
+
+// Uniform grid broadphase with one cell per tile. Colliders are bucketed by the
+// cells their AABB touches, so a lookup only has to look at the neighbourhood.
+#[derive(Resource, Default)]
+pub struct SpatialGrid {
+    cells: HashMap<IVec2, Vec<Entity>>,
+    entries: HashMap<Entity, CellRange>,
+}
+
+impl SpatialGrid {
+    // Adds the entity, or moves it if it is already in the grid
+    pub fn insert(&mut self, entity: Entity, position: Vec2, size: Vec2) {
+        let range = CellRange::of(position, size);
+        // Most frames an object stays inside the same cells
+        if self.entries.get(&entity) == Some(&range) {
+            return;
+        }
+
+        self.remove(entity);
+        for cell in range.cells() {
+            self.cells.entry(cell).or_default().push(entity);
+        }
+        self.entries.insert(entity, range);
+    }
+
+    pub fn remove(&mut self, entity: Entity) {
+        let Some(range) = self.entries.remove(&entity) else {
+            return;
+        };
+
+        for cell in range.cells() {
+            if let Some(bucket) = self.cells.get_mut(&cell) {
+                bucket.retain(|&other| other != entity);
+                if bucket.is_empty() {
+                    self.cells.remove(&cell);
+                }
+            }
+        }
+    }
+
+    // Every entity sharing a cell with the AABB, each one once.
+    // These are only candidates, the caller still has to do the exact check.
+    pub fn query(&self, position: Vec2, size: Vec2) -> Vec<Entity> {
+        let mut found: Vec<Entity> = CellRange::of(position, size)
+            .cells()
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+            .collect();
+
+        found.sort_unstable();
+        found.dedup();
+        found
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.entries.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIZE: Vec2 = Vec2::new(8.0, 8.0);
+
+    fn entity(index: u32) -> Entity {
+        Entity::from_raw(index)
+    }
+
+    #[test]
+    fn moving_across_a_cell_boundary_moves_the_entity() {
+        let mut grid = SpatialGrid::default();
+        let left = Vec2::new(TILE_SIZE * 0.5, TILE_SIZE * 0.5);
+        let right = Vec2::new(TILE_SIZE * 1.5, TILE_SIZE * 0.5);
+
+        grid.insert(entity(1), left, SIZE);
+        // Still inside the same cell
+        grid.insert(entity(1), left + Vec2::new(4.0, 0.0), SIZE);
+        assert_eq!(grid.query(left, SIZE), vec![entity(1)]);
+
+        grid.insert(entity(1), right, SIZE);
+        assert!(grid.query(left, SIZE).is_empty());
+        assert_eq!(grid.query(right, SIZE), vec![entity(1)]);
+        assert_eq!(grid.len(), 1);
+    }
+
+    #[test]
+    fn removed_entities_are_not_found() {
+        let mut grid = SpatialGrid::default();
+        grid.insert(entity(1), Vec2::ZERO, SIZE);
+        grid.remove(entity(1));
+        // Removing twice does nothing
+        grid.remove(entity(1));
+
+        assert!(grid.query(Vec2::ZERO, SIZE).is_empty());
+        assert!(grid.is_empty());
+    }
+
+    #[test]
+    fn a_collider_over_several_cells_is_found_once() {
+        let mut grid = SpatialGrid::default();
+        grid.insert(entity(1), Vec2::ZERO, Vec2::splat(TILE_SIZE * 3.0));
+        grid.insert(entity(2), Vec2::splat(TILE_SIZE * 10.0), SIZE);
+
+        assert_eq!(grid.query(Vec2::ZERO, Vec2::splat(TILE_SIZE * 4.0)), vec![entity(1)]);
+    }
+
+    #[test]
+    fn queries_include_the_cells_at_both_ends() {
+        let mut grid = SpatialGrid::default();
+        grid.insert(entity(1), Vec2::new(-TILE_SIZE * 1.5, 0.0), SIZE);
+        grid.insert(entity(2), Vec2::new(TILE_SIZE * 1.5, 0.0), SIZE);
+
+        // Reaches just into the cells on either side
+        let found = grid.query(Vec2::ZERO, Vec2::new(TILE_SIZE * 2.0 + 2.0, 1.0));
+        assert_eq!(found, vec![entity(1), entity(2)]);
+    }
+}
@@ -0,0 +1,162 @@
+use std::collections::VecDeque;
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use crate::background::generate_terrain;
+use crate::environment::generate_scenery;
+use crate::moving_road::{
+    generate_road, row_spans, row_y, RoadGenerator, RoadRing, RoadRowTile, RoadGraph, RoadSpan,
+};
+use crate::obstacle_chunks::{generate_obstacles, ObstacleGenerator};
+use crate::pickup::generate_pickups;
+use crate::pool::{EntityPool, Parked, Pooled, RECYCLE_Y};
+use crate::world_grid::{GridConfig, GRID_SIZE_Y, TILE_SIZE};
+use crate::world_seed::WorldRng;
+
+// The world is generated and retired in chunks of this many tile rows
+pub const CHUNK_ROWS: u32 = 8;
+// A chunk is generated once its bottom row is a tile above the screen
+const CHUNK_SPAWN_Y: f32 = GRID_SIZE_Y as f32 * TILE_SIZE / 2.0 + TILE_SIZE;
+
+// Something spawned for a chunk, it goes back to the pool when the chunk is retired
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ChunkMember(pub u64);
+
+// The chunk being generated, handed from one stage of the pipeline to the next
+pub struct ChunkContext {
+    pub index: u64,
+    // The road row the chunk starts at, rows count up from the bottom of the first screen
+    pub first_row: u64,
+    // Where the chunk's bottom row is right now
+    pub bottom_y: f32,
+    // The road tiles of each row, from the bottom up. Filled in by the road stage.
+    pub road: Vec<Vec<RoadRowTile>>,
+    // The rows already generated above the chunk, from the bottom up
+    pub road_ahead: Vec<Vec<RoadRowTile>>,
+}
+
+impl ChunkContext {
+    pub fn row_y(&self, row: u32) -> f32 {
+        self.bottom_y + row as f32 * TILE_SIZE
+    }
+
+    // Rows past the top of the chunk come from `road_ahead`
+    pub fn spans(&self, row: u32) -> Vec<RoadSpan> {
+        let row = row as usize;
+        let tiles = match row.checked_sub(self.road.len()) {
+            Some(ahead) => self.road_ahead.get(ahead),
+            None => self.road.get(row),
+        };
+        tiles.map_or_else(Vec::new, |tiles| row_spans(tiles))
+    }
+}
+
+// What the pipeline stages spawn their entities through
+pub struct ChunkSpawner<'a, 'w, 's> {
+    commands: &'a mut Commands<'w, 's>,
+    pool: &'a mut EntityPool,
+    pub asset_server: &'a AssetServer,
+    pub chunk: ChunkContext,
+}
+
+impl ChunkSpawner<'_, '_, '_> {
+    // Spawns `bundle` into an entity from the pool under `key`, as a member of the chunk
+    pub fn spawn(&mut self, key: &'static str, bundle: impl Bundle) -> Entity {
+        let member = ChunkMember(self.chunk.index);
+        self.pool.take(self.commands, key).insert((bundle, member)).id()
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct WorldChunks {
+    // The next chunk to generate
+    next: u64,
+    // Chunks that have been generated and not retired yet, oldest first
+    live: VecDeque<u64>,
+}
+
+// What the stages of the pipeline carry over from one chunk to the next
+#[derive(SystemParam)]
+pub struct Generators<'w> {
+    ring: ResMut<'w, RoadRing>,
+    road: ResMut<'w, RoadGenerator>,
+    road_graph: ResMut<'w, RoadGraph>,
+    obstacles: ResMut<'w, ObstacleGenerator>,
+    rng: ResMut<'w, WorldRng>,
+}
+
+pub fn setup_world_chunks(mut commands: Commands) {
+    commands.insert_resource(WorldChunks::default());
+}
+
+// Generates every chunk that's about to scroll onto the screen
+pub fn spawn_chunks(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    grid_config: Res<GridConfig>,
+    mut chunks: ResMut<WorldChunks>,
+    mut pool: ResMut<EntityPool>,
+    mut generators: Generators,
+) {
+    let scroll_px = grid_config.scroll_px();
+
+    loop {
+        let index = chunks.next;
+        let first_row = index * CHUNK_ROWS as u64;
+        let bottom_y = row_y(first_row, scroll_px);
+        if bottom_y >= CHUNK_SPAWN_Y {
+            break;
+        }
+
+        let mut spawner = ChunkSpawner {
+            commands: &mut commands,
+            pool: &mut pool,
+            asset_server: &asset_server,
+            chunk: ChunkContext { index, first_row, bottom_y, road: Vec::new(), road_ahead: Vec::new() },
+        };
+        let Generators { ring, road, road_graph, obstacles, rng } = &mut generators;
+        let speed = grid_config.scroll_speed;
+
+        generate_terrain(&mut spawner);
+        generate_road(&mut spawner.chunk, road, road_graph, ring, &mut rng.road);
+        generate_scenery(&mut spawner, &mut rng.environment);
+        generate_obstacles(&mut spawner, obstacles, road_graph, speed, &mut rng.obstacles);
+        generate_pickups(&mut spawner, road_graph, speed, &mut rng.pickups);
+
+        chunks.live.push_back(index);
+        chunks.next += 1;
+    }
+}
+
+// Parks everything a chunk spawned and drops its road rows once its top row is below the screen
+pub fn retire_chunks(
+    mut commands: Commands,
+    grid_config: Res<GridConfig>,
+    mut chunks: ResMut<WorldChunks>,
+    mut pool: ResMut<EntityPool>,
+    mut ring: ResMut<RoadRing>,
+    member_query: Query<(Entity, &ChunkMember, &Pooled), Without<Parked>>,
+) {
+    let scroll_px = grid_config.scroll_px();
+
+    let mut retired = Vec::new();
+    while let Some(&index) = chunks.live.front() {
+        let top_row = (index + 1) * CHUNK_ROWS as u64 - 1;
+        if row_y(top_row, scroll_px) >= RECYCLE_Y {
+            break;
+        }
+        retired.push(index);
+        chunks.live.pop_front();
+    }
+
+    let Some(&last) = retired.last() else {
+        return;
+    };
+    ring.retire_before((last + 1) * CHUNK_ROWS as u64);
+
+    for (entity, member, &Pooled(key)) in member_query.iter() {
+        if retired.contains(&member.0) {
+            pool.park(&mut commands, entity, key);
+        }
+    }
+}
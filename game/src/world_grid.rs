@@ -1,5 +1,7 @@
 use bevy::prelude::*;
 use crate::scoreboard::{ScoreText, ScoreTimer};
+use crate::collision::Collider;
+use crate::spatial_grid::SpatialGrid;
 
 // Z-index constants for layering
 pub const GRID_Z: f32 = 0.0;
@@ -14,9 +16,8 @@ pub const GRID_SIZE_X: u32 = 15;
 pub const GRID_SIZE_Y: u32 = 15;
 pub const TILE_SIZE: f32 = 32.0;
 
-// Screen boundaries
-pub const SCREEN_BOTTOM: f32 = -320.0;
-//pub const SCREEN_TOP: f32 = 250.0;
+// The speed stops increasing here
+pub const MAX_SCROLL_SPEED: f32 = 300.0;
 
 #[derive(Component)]
 /*pub struct WorldGrid;
@@ -40,8 +41,17 @@ pub struct GridConfig {
     pub grid_width: u32,
     pub grid_height: u32,
     pub scroll_speed: f32,
+    // How far grid objects moved down in the last frame, for swept collision
+    pub frame_scroll: f32,
+    // Total scroll since the run started, never reset
     pub distance_moved: f32,
-    pub spawn_threshold: f32,
+}
+
+impl GridConfig {
+    // The scroll in whole pixels, what everything on screen is actually moved by
+    pub fn scroll_px(&self) -> f32 {
+        self.distance_moved.floor()
+    }
 }
 
 #[derive(Component)]
@@ -65,8 +75,8 @@ pub fn setup_world_grid(mut commands: Commands) {
         grid_width: GRID_SIZE_X,
         grid_height: GRID_SIZE_Y,
         scroll_speed: 35.0,
+        frame_scroll: 0.0,
         distance_moved: 0.0,
-        spawn_threshold: TILE_SIZE * 10.0,
     });
     
     // Initialize debug grid configuration (ON by default)
@@ -81,54 +91,29 @@ pub fn setup_world_grid(mut commands: Commands) {
 pub fn move_grid_objects(
     time: Res<Time>,
     mut grid_config: ResMut<GridConfig>,
-    mut query: Query<&mut Transform, With<GridObject>>,
+    mut query: Query<(Entity, &mut Transform, Option<&Collider>), With<GridObject>>,
+    mut spatial_grid: ResMut<SpatialGrid>,
 ) {
     // Begræns delta_time for at undgå store spikes
     let delta_time = time.delta_secs().min(1.0 / 60.0);
 
     let movement = grid_config.scroll_speed * delta_time;
+    let previous_px = grid_config.scroll_px();
     grid_config.distance_moved += movement;
+    // Everything moves by the same whole number of pixels, so nothing drifts apart
+    // and the total always adds up to scroll_px()
+    let step = grid_config.scroll_px() - previous_px;
+    grid_config.frame_scroll = step;
 
-    for mut transform in query.iter_mut() {
-        transform.translation.y -= movement;
+    for (entity, mut transform, collider) in query.iter_mut() {
+        transform.translation.y -= step;
 
         // Snap to pixel grid for crisp rendering
         transform.translation.x = transform.translation.x.floor();
         transform.translation.y = transform.translation.y.floor();
-    }
-}
-
-
-
-// Add this system to clean up offscreen objects and spawn new ones
-pub fn check_offscreen_objects(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    mut query: Query<(Entity, &Transform, &GridObject)>,
-    mut grid_config: ResMut<GridConfig>,
-) {
-    let mut should_spawn_new = false;
-
-    // Check if objects have moved off screen
-    for (entity, transform, _) in query.iter() {
-        if transform.translation.y < SCREEN_BOTTOM {
-            commands.entity(entity).despawn();
-            should_spawn_new = true;
-        }
-    }
-
-    // Update distance counter
-    if should_spawn_new {
-        grid_config.distance_moved += TILE_SIZE;
-
-        // Check if we've moved enough to spawn new objects
-        if grid_config.distance_moved >= grid_config.spawn_threshold {
-            // Reset counter
-            grid_config.distance_moved = 0.0;
 
-            // This is where you would add logic to spawn new objects
-            // For now, we'll just log that we should spawn something
-            println!("Should spawn new objects at the top of the screen");
+        if let Some(collider) = collider {
+            spatial_grid.insert(entity, transform.translation.truncate(), collider.size);
         }
     }
 }
@@ -217,7 +202,7 @@ pub fn increase_scroll_speed(
     score_timer.0.tick(time.delta());
 
     if score_timer.0.just_finished() {
-        grid_config.scroll_speed += 10.;
+        grid_config.scroll_speed = (grid_config.scroll_speed + 10.).min(MAX_SCROLL_SPEED);
         println!("Scroll speed: {}", grid_config.scroll_speed);
     }
 }
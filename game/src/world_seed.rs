@@ -0,0 +1,88 @@
+use bevy::prelude::*;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use crate::cli;
+use crate::daily::{daily_seed, days_since_epoch, GameMode};
+
+const SEED_FLAG: &str = "--seed";
+
+// Every spawner draws from its own stream, so adding a random call to one
+// spawner doesn't change what the others generate for the same seed
+const ENVIRONMENT_STREAM: u64 = 0;
+const OBSTACLE_STREAM: u64 = 1;
+const PICKUP_STREAM: u64 = 2;
+const ROAD_STREAM: u64 = 3;
+
+// The seed the current run's world is generated from
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct WorldSeed {
+    pub seed: u64,
+    // Set with --seed, every restart replays the same world
+    pub fixed: bool,
+}
+
+impl WorldSeed {
+    pub fn load(mode: &GameMode) -> Self {
+        if let GameMode::Daily { day, .. } = mode {
+            if cli::flag_value(SEED_FLAG).is_some() {
+                warn!("--seed ignoreres i daily run");
+            }
+            return Self { seed: daily_seed(*day), fixed: true };
+        }
+
+        match cli::flag_value(SEED_FLAG).map(|value| value.parse::<u64>()) {
+            Some(Ok(seed)) => Self { seed, fixed: true },
+            Some(Err(_)) => {
+                warn!("Ugyldigt seed, bruger et tilfældigt");
+                Self::random()
+            }
+            None => Self::random(),
+        }
+    }
+
+    pub fn random() -> Self {
+        Self { seed: rand::thread_rng().gen(), fixed: false }
+    }
+}
+
+#[derive(Resource)]
+pub struct WorldRng {
+    pub environment: ChaCha8Rng,
+    pub obstacles: ChaCha8Rng,
+    pub pickups: ChaCha8Rng,
+    pub road: ChaCha8Rng,
+}
+
+impl WorldRng {
+    pub fn new(seed: u64) -> Self {
+        let stream = |stream| {
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            rng.set_stream(stream);
+            rng
+        };
+
+        Self {
+            environment: stream(ENVIRONMENT_STREAM),
+            obstacles: stream(OBSTACLE_STREAM),
+            pickups: stream(PICKUP_STREAM),
+            road: stream(ROAD_STREAM),
+        }
+    }
+}
+
+// Runs first on every (re)start, before anything is spawned
+pub fn setup_world_rng(
+    mut world_seed: ResMut<WorldSeed>,
+    mut world_rng: ResMut<WorldRng>,
+    mut mode: ResMut<GameMode>,
+) {
+    mode.roll_over_to(days_since_epoch());
+    if let GameMode::Daily { day, .. } = *mode {
+        world_seed.seed = daily_seed(day);
+    } else if !world_seed.fixed {
+        *world_seed = WorldSeed::random();
+    }
+    info!("Seed: {}", world_seed.seed);
+
+    *world_rng = WorldRng::new(world_seed.seed);
+}
@@ -0,0 +1,108 @@
+// Runs the real serial listener against a fake micro:bit on a pseudo-terminal.
+#![cfg(unix)]
+
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+use jump_and_run_platform::character::{jump, AnimationConfig, Player};
+use jump_and_run_platform::fake_microbit::FakeMicrobit;
+use jump_and_run_platform::input::{update_action_state, ActionMap, ActionState};
+use jump_and_run_platform::microbit::{
+    drain_controller_events, setup_serial_listener, ControllerChannel, ControllerEvent,
+    MalformedLineCount, SerialOutbox, SerialSettings, SharedSerialState,
+};
+use microbit_protocol::{Button, Message};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+fn app_listening_on(board: &FakeMicrobit) -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_event::<ControllerEvent>()
+        .init_resource::<ControllerChannel>()
+        .init_resource::<SerialOutbox>()
+        .init_resource::<SharedSerialState>()
+        .init_resource::<MalformedLineCount>()
+        .init_resource::<ButtonInput<KeyCode>>()
+        .init_resource::<ActionMap>()
+        .init_resource::<ActionState>()
+        .insert_resource(SerialSettings::with_port(board.port_name()))
+        .add_systems(Startup, setup_serial_listener)
+        .add_systems(PreUpdate, (drain_controller_events, update_action_state).chain())
+        .add_systems(Update, jump);
+
+    app.world_mut().spawn((
+        Player::default(),
+        AnimationConfig::new(default(), default(), (0, 5), default(), default(), (0, 2), 10),
+        Sprite::default(),
+    ));
+    app
+}
+
+// Keeps the app running until the condition holds, so frame times stay short
+fn update_until(app: &mut App, condition: impl Fn(&mut App) -> bool) -> bool {
+    let deadline = Instant::now() + TIMEOUT;
+
+    while Instant::now() < deadline {
+        app.update();
+        if condition(app) {
+            return true;
+        }
+        thread::sleep(Duration::from_millis(5));
+    }
+    false
+}
+
+fn player_is_jumping(app: &mut App) -> bool {
+    let mut query = app.world_mut().query::<&Player>();
+    query.single(app.world()).is_jumping
+}
+
+#[test]
+fn button_a_on_the_board_makes_the_player_jump() {
+    let mut board = FakeMicrobit::open().expect("could not open a pseudo-terminal");
+    let mut app = app_listening_on(&board);
+
+    app.update();
+    board.wait_for_game(TIMEOUT).unwrap();
+    app.update();
+    assert!(!player_is_jumping(&mut app));
+
+    board.send(Message::Button { button: Button::A, pressed: true }).unwrap();
+
+    assert!(update_until(&mut app, player_is_jumping), "the jump never reached the player");
+}
+
+#[test]
+fn legacy_jump_line_still_works() {
+    let mut board = FakeMicrobit::open().expect("could not open a pseudo-terminal");
+    let mut app = app_listening_on(&board);
+
+    app.update();
+    board.wait_for_game(TIMEOUT).unwrap();
+    app.update();
+
+    board.send_raw("JUMP").unwrap();
+
+    assert!(update_until(&mut app, player_is_jumping), "the jump never reached the player");
+}
+
+#[test]
+fn malformed_lines_are_counted_and_ignored() {
+    let mut board = FakeMicrobit::open().expect("could not open a pseudo-terminal");
+    let mut app = app_listening_on(&board);
+
+    app.update();
+    board.wait_for_game(TIMEOUT).unwrap();
+
+    board.send_raw("BTN A DOWN*00").unwrap();
+    board.send_raw("hello there").unwrap();
+
+    let counted = update_until(&mut app, |app| {
+        app.world().resource::<MalformedLineCount>().0.load(Ordering::Relaxed) == 2
+    });
+    assert!(counted, "malformed lines were not counted");
+    assert!(!player_is_jumping(&mut app));
+}
@@ -0,0 +1,432 @@
+// Line protocol spoken between the micro:bit and the game.
+//
+// Every line is `<body>*<checksum>`, where the checksum is the XOR of all body
+// bytes written as two hex digits, e.g. `BTN A DOWN*0B`. The board starts with
+// `HELLO v1` so the game knows which version it is talking to. A bare `JUMP`
+// without checksum is still accepted from boards running the old firmware.
+//
+// `Message` is what the board sends, `Command` is what the game sends back
+// for the LED matrix. The game says `HELLO v1` when it opens the port and the
+// board answers with its own `HELLO`; the game never answers a `HELLO`.
+//
+// Shared by the game and the firmware, so only `core` is used.
+
+#![cfg_attr(not(test), no_std)]
+
+use core::fmt::{self, Write};
+use core::str::SplitWhitespace;
+
+pub const PROTOCOL_VERSION: u8 = 1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Button {
+    A,
+    B,
+    Logo,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Gesture {
+    Shake,
+    Left,
+    Right,
+    Up,
+    Down,
+    FaceUp,
+    FaceDown,
+    FreeFall,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Message {
+    Hello { version: u8 },
+    Button { button: Button, pressed: bool },
+    // Accelerometer reading in milli-g
+    Accel { x: i16, y: i16, z: i16 },
+    Gesture(Gesture),
+    LegacyJump,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Icon {
+    Heart,
+    Happy,
+    Sad,
+    Skull,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Command<'a> {
+    Hello { version: u8 },
+    Icon(Icon),
+    // Scrolls the number across the display
+    Score(u32),
+    Scroll(&'a str),
+    Clear,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParseError {
+    Empty,
+    MissingChecksum,
+    InvalidChecksum,
+    ChecksumMismatch { expected: u8, found: u8 },
+    UnknownCommand,
+    MissingArgument,
+    InvalidArgument,
+    TrailingData,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Empty => write!(f, "empty line"),
+            ParseError::MissingChecksum => write!(f, "missing checksum"),
+            ParseError::InvalidChecksum => write!(f, "checksum is not two hex digits"),
+            ParseError::ChecksumMismatch { expected, found } => {
+                write!(f, "checksum mismatch (expected {:02X}, found {:02X})", expected, found)
+            }
+            ParseError::UnknownCommand => write!(f, "unknown command"),
+            ParseError::MissingArgument => write!(f, "missing argument"),
+            ParseError::InvalidArgument => write!(f, "invalid argument"),
+            ParseError::TrailingData => write!(f, "unexpected trailing data"),
+        }
+    }
+}
+
+impl Button {
+    fn name(&self) -> &'static str {
+        match self {
+            Button::A => "A",
+            Button::B => "B",
+            Button::Logo => "LOGO",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "A" => Some(Button::A),
+            "B" => Some(Button::B),
+            "LOGO" => Some(Button::Logo),
+            _ => None,
+        }
+    }
+}
+
+impl Gesture {
+    fn name(&self) -> &'static str {
+        match self {
+            Gesture::Shake => "shake",
+            Gesture::Left => "left",
+            Gesture::Right => "right",
+            Gesture::Up => "up",
+            Gesture::Down => "down",
+            Gesture::FaceUp => "faceup",
+            Gesture::FaceDown => "facedown",
+            Gesture::FreeFall => "freefall",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "shake" => Some(Gesture::Shake),
+            "left" => Some(Gesture::Left),
+            "right" => Some(Gesture::Right),
+            "up" => Some(Gesture::Up),
+            "down" => Some(Gesture::Down),
+            "faceup" => Some(Gesture::FaceUp),
+            "facedown" => Some(Gesture::FaceDown),
+            "freefall" => Some(Gesture::FreeFall),
+            _ => None,
+        }
+    }
+}
+
+impl Icon {
+    fn name(&self) -> &'static str {
+        match self {
+            Icon::Heart => "heart",
+            Icon::Happy => "happy",
+            Icon::Sad => "sad",
+            Icon::Skull => "skull",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "heart" => Some(Icon::Heart),
+            "happy" => Some(Icon::Happy),
+            "sad" => Some(Icon::Sad),
+            "skull" => Some(Icon::Skull),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Command<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Command::Hello { version } => write!(f, "HELLO v{}", version),
+            Command::Icon(icon) => write!(f, "ICON {}", icon.name()),
+            Command::Score(score) => write!(f, "SCORE {}", score),
+            Command::Scroll(text) => write!(f, "SCROLL {}", text),
+            Command::Clear => write!(f, "CLEAR"),
+        }
+    }
+}
+
+// Writes the message body, without checksum
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Message::Hello { version } => write!(f, "HELLO v{}", version),
+            Message::Button { button, pressed } => {
+                write!(f, "BTN {} {}", button.name(), if *pressed { "DOWN" } else { "UP" })
+            }
+            Message::Accel { x, y, z } => write!(f, "ACC {} {} {}", x, y, z),
+            Message::Gesture(gesture) => write!(f, "GESTURE {}", gesture.name()),
+            Message::LegacyJump => write!(f, "JUMP"),
+        }
+    }
+}
+
+pub fn checksum(body: &str) -> u8 {
+    body.bytes().fold(0, |acc, byte| acc ^ byte)
+}
+
+// Writes a complete line (body and checksum), without the trailing newline
+pub fn write_line<W: fmt::Write>(out: &mut W, body: impl fmt::Display) -> fmt::Result {
+    let mut writer = ChecksumWriter { inner: out, checksum: 0 };
+    write!(writer, "{}", body)?;
+    let checksum = writer.checksum;
+    write!(out, "*{:02X}", checksum)
+}
+
+struct ChecksumWriter<'a, W: fmt::Write> {
+    inner: &'a mut W,
+    checksum: u8,
+}
+
+impl<W: fmt::Write> fmt::Write for ChecksumWriter<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.checksum ^= checksum(s);
+        self.inner.write_str(s)
+    }
+}
+
+// Splits `<body>*<checksum>` and verifies the checksum
+pub fn verify_checksum(line: &str) -> Result<&str, ParseError> {
+    let line = line.trim();
+    if line.is_empty() {
+        return Err(ParseError::Empty);
+    }
+
+    let (body, sum) = line.rsplit_once('*').ok_or(ParseError::MissingChecksum)?;
+    if sum.len() != 2 {
+        return Err(ParseError::InvalidChecksum);
+    }
+    let found = u8::from_str_radix(sum, 16).map_err(|_| ParseError::InvalidChecksum)?;
+    let expected = checksum(body);
+
+    if expected != found {
+        return Err(ParseError::ChecksumMismatch { expected, found });
+    }
+    Ok(body)
+}
+
+pub fn parse_line(line: &str) -> Result<Message, ParseError> {
+    if line.trim() == "JUMP" {
+        return Ok(Message::LegacyJump);
+    }
+
+    let body = verify_checksum(line)?;
+    let mut parts = body.split_whitespace();
+
+    let message = match parts.next().ok_or(ParseError::Empty)? {
+        "HELLO" => {
+            let version = next_arg(&mut parts)?
+                .strip_prefix('v')
+                .and_then(|v| v.parse().ok())
+                .ok_or(ParseError::InvalidArgument)?;
+            Message::Hello { version }
+        }
+        "BTN" => {
+            let button = Button::from_name(next_arg(&mut parts)?).ok_or(ParseError::InvalidArgument)?;
+            let pressed = match next_arg(&mut parts)? {
+                "DOWN" => true,
+                "UP" => false,
+                _ => return Err(ParseError::InvalidArgument),
+            };
+            Message::Button { button, pressed }
+        }
+        "ACC" => Message::Accel {
+            x: parse_arg(&mut parts)?,
+            y: parse_arg(&mut parts)?,
+            z: parse_arg(&mut parts)?,
+        },
+        "GESTURE" => {
+            let gesture = Gesture::from_name(next_arg(&mut parts)?).ok_or(ParseError::InvalidArgument)?;
+            Message::Gesture(gesture)
+        }
+        _ => return Err(ParseError::UnknownCommand),
+    };
+
+    if parts.next().is_some() {
+        return Err(ParseError::TrailingData);
+    }
+    Ok(message)
+}
+
+pub fn parse_command(line: &str) -> Result<Command<'_>, ParseError> {
+    let body = verify_checksum(line)?;
+    let (name, rest) = body.split_once(' ').unwrap_or((body, ""));
+
+    // Scroll text is free-form, so it is not split into words
+    if name == "SCROLL" {
+        return Ok(Command::Scroll(rest));
+    }
+
+    let mut parts = rest.split_whitespace();
+    let command = match name {
+        "HELLO" => {
+            let version = next_arg(&mut parts)?
+                .strip_prefix('v')
+                .and_then(|v| v.parse().ok())
+                .ok_or(ParseError::InvalidArgument)?;
+            Command::Hello { version }
+        }
+        "ICON" => Command::Icon(Icon::from_name(next_arg(&mut parts)?).ok_or(ParseError::InvalidArgument)?),
+        "SCORE" => Command::Score(parse_arg(&mut parts)?),
+        "CLEAR" => Command::Clear,
+        "" => return Err(ParseError::Empty),
+        _ => return Err(ParseError::UnknownCommand),
+    };
+
+    if parts.next().is_some() {
+        return Err(ParseError::TrailingData);
+    }
+    Ok(command)
+}
+
+fn next_arg<'a>(parts: &mut SplitWhitespace<'a>) -> Result<&'a str, ParseError> {
+    parts.next().ok_or(ParseError::MissingArgument)
+}
+
+fn parse_arg<T: core::str::FromStr>(parts: &mut SplitWhitespace<'_>) -> Result<T, ParseError> {
+    next_arg(parts)?.parse().map_err(|_| ParseError::InvalidArgument)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(body: &str) -> String {
+        let mut out = String::new();
+        write_line(&mut out, body).unwrap();
+        out
+    }
+
+    #[test]
+    fn checksum_is_xor_of_body() {
+        assert_eq!(checksum(""), 0);
+        assert_eq!(checksum("A"), b'A');
+        assert_eq!(checksum("AB"), b'A' ^ b'B');
+        assert_eq!(line("HELLO v1"), format!("HELLO v1*{:02X}", checksum("HELLO v1")));
+    }
+
+    #[test]
+    fn parses_every_message() {
+        assert_eq!(parse_line(&line("HELLO v1")), Ok(Message::Hello { version: 1 }));
+        assert_eq!(
+            parse_line(&line("BTN A DOWN")),
+            Ok(Message::Button { button: Button::A, pressed: true })
+        );
+        assert_eq!(
+            parse_line(&line("BTN LOGO UP")),
+            Ok(Message::Button { button: Button::Logo, pressed: false })
+        );
+        assert_eq!(
+            parse_line(&line("ACC -512 0 1024")),
+            Ok(Message::Accel { x: -512, y: 0, z: 1024 })
+        );
+        assert_eq!(parse_line(&line("GESTURE shake")), Ok(Message::Gesture(Gesture::Shake)));
+    }
+
+    #[test]
+    fn written_messages_parse_back() {
+        let messages = [
+            Message::Hello { version: PROTOCOL_VERSION },
+            Message::Button { button: Button::B, pressed: true },
+            Message::Accel { x: 12, y: -340, z: -1020 },
+            Message::Gesture(Gesture::FaceDown),
+        ];
+
+        for message in messages {
+            let mut out = String::new();
+            write_line(&mut out, message).unwrap();
+            assert_eq!(parse_line(&out), Ok(message), "{}", out);
+        }
+    }
+
+    #[test]
+    fn accepts_legacy_jump_and_line_endings() {
+        assert_eq!(parse_line("JUMP"), Ok(Message::LegacyJump));
+        assert_eq!(parse_line("JUMP\r\n"), Ok(Message::LegacyJump));
+        assert_eq!(parse_line(&format!("{}\r\n", line("GESTURE left"))), Ok(Message::Gesture(Gesture::Left)));
+    }
+
+    #[test]
+    fn rejects_bad_checksums() {
+        assert_eq!(parse_line("BTN A DOWN"), Err(ParseError::MissingChecksum));
+        assert_eq!(parse_line("BTN A DOWN*G1"), Err(ParseError::InvalidChecksum));
+        assert_eq!(parse_line("BTN A DOWN*1"), Err(ParseError::InvalidChecksum));
+
+        let expected = checksum("BTN A DOWN");
+        let found = expected ^ 0xFF;
+        assert_eq!(
+            parse_line(&format!("BTN A DOWN*{:02X}", found)),
+            Err(ParseError::ChecksumMismatch { expected, found })
+        );
+    }
+
+    #[test]
+    fn commands_parse_back() {
+        let commands = [
+            Command::Hello { version: PROTOCOL_VERSION },
+            Command::Icon(Icon::Skull),
+            Command::Score(1200),
+            Command::Scroll("NEW HIGH SCORE 340"),
+            Command::Clear,
+        ];
+
+        for command in commands {
+            let mut out = String::new();
+            write_line(&mut out, command).unwrap();
+            assert_eq!(parse_command(&out), Ok(command), "{}", out);
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_commands() {
+        assert_eq!(parse_command("ICON heart"), Err(ParseError::MissingChecksum));
+        assert_eq!(parse_command(&line("ICON dragon")), Err(ParseError::InvalidArgument));
+        assert_eq!(parse_command(&line("SCORE -5")), Err(ParseError::InvalidArgument));
+        assert_eq!(parse_command(&line("CLEAR now")), Err(ParseError::TrailingData));
+        assert_eq!(parse_command(&line("BLINK")), Err(ParseError::UnknownCommand));
+    }
+
+    #[test]
+    fn rejects_malformed_bodies() {
+        assert_eq!(parse_line(""), Err(ParseError::Empty));
+        assert_eq!(parse_line("   "), Err(ParseError::Empty));
+        assert_eq!(parse_line(&line("FLY away")), Err(ParseError::UnknownCommand));
+        assert_eq!(parse_line(&line("BTN C DOWN")), Err(ParseError::InvalidArgument));
+        assert_eq!(parse_line(&line("BTN A")), Err(ParseError::MissingArgument));
+        assert_eq!(parse_line(&line("ACC 1 2")), Err(ParseError::MissingArgument));
+        assert_eq!(parse_line(&line("ACC 1 2 lots")), Err(ParseError::InvalidArgument));
+        assert_eq!(parse_line(&line("ACC 1 2 99999")), Err(ParseError::InvalidArgument));
+        assert_eq!(parse_line(&line("HELLO 1")), Err(ParseError::InvalidArgument));
+        assert_eq!(parse_line(&line("GESTURE wave")), Err(ParseError::InvalidArgument));
+        assert_eq!(parse_line(&line("GESTURE shake shake")), Err(ParseError::TrailingData));
+    }
+}